@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ptp::{ObjectInfo, ObjectInfoRef};
+
+fn sample() -> Vec<u8> {
+    ObjectInfo {
+        StorageID: 0x0001_0001,
+        ObjectFormat: 0x3801,
+        ProtectionStatus: 0,
+        ObjectCompressedSize: 6_291_456,
+        ThumbFormat: 0x3808,
+        ThumbCompressedSize: 8192,
+        ThumbPixWidth: 160,
+        ThumbPixHeight: 120,
+        ImagePixWidth: 6000,
+        ImagePixHeight: 4000,
+        ImageBitDepth: 24,
+        ParentObject: 0x9000_0001,
+        AssociationType: 0,
+        AssociationDesc: 0,
+        SequenceNumber: 0,
+        Filename: "DSC01234.JPG".to_string(),
+        CaptureDate: "20240612T143015".to_string(),
+        ModificationDate: "20240612T143015".to_string(),
+        Keywords: String::new(),
+    }
+    .encode()
+    .unwrap()
+}
+
+fn object_info(c: &mut Criterion) {
+    let buf = sample();
+    let mut group = c.benchmark_group("object_info");
+    group.bench_function("decode", |b| {
+        b.iter(|| ObjectInfo::decode(black_box(&buf)).unwrap())
+    });
+    group.bench_function("view", |b| {
+        b.iter(|| ObjectInfoRef::parse(black_box(&buf)).unwrap())
+    });
+    group.bench_function("view_size_and_filename", |b| {
+        b.iter(|| {
+            let info = ObjectInfoRef::parse(black_box(&buf)).unwrap();
+            (
+                info.object_compressed_size(),
+                info.filename().eq_str("DSC01234.JPG"),
+            )
+        })
+    });
+    group.bench_function("view_to_object_info", |b| {
+        b.iter(|| {
+            ObjectInfoRef::parse(black_box(&buf))
+                .unwrap()
+                .to_object_info()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, object_info);
+criterion_main!(benches);
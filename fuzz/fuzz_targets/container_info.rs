@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ptp::ContainerInfo;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ContainerInfo::parse(data);
+});
@@ -0,0 +1,17 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ptp::DataType;
+use std::io::Cursor;
+
+// the first two bytes select the type code, the rest is the value
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 2 {
+        return;
+    }
+    let kind = u16::from_le_bytes([data[0], data[1]]);
+    if let Ok(value) = DataType::read_type(kind, &mut Cursor::new(&data[2..])) {
+        let bytes = value.encode().unwrap();
+        let decoded = DataType::read_type(kind, &mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(decoded, value);
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ptp::{ObjectInfo, PtpEncode};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(info) = ObjectInfo::decode(data) {
+        // whatever decodes must encode again, and decode to the same value
+        let bytes = info.to_bytes().unwrap();
+        assert_eq!(ObjectInfo::decode(&bytes).unwrap(), info);
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use ptp::{PtpEncode, StorageInfo};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(info) = StorageInfo::decode(&mut Cursor::new(data)) {
+        // whatever decodes must encode again, and decode to the same value
+        let bytes = info.to_bytes().unwrap();
+        assert_eq!(StorageInfo::decode(&mut Cursor::new(&bytes)).unwrap(), info);
+    }
+});
@@ -0,0 +1,239 @@
+//! `#[derive(PtpDataset)]` for the `ptp` crate.
+//!
+//! Implements `ptp::PtpDecode` and `ptp::PtpEncode` for a struct with named fields, reading and
+//! writing the fields in declaration order. Field types are encoded through their own
+//! `PtpDecode`/`PtpEncode` implementations: integers little-endian, `String` as a PTP string,
+//! `Vec<T>` as a PTP array. Fields can be adjusted with `#[ptp(...)]`:
+//!
+//! * `data_type = "field"`: for `DataType` and `FormData` fields, the earlier `u16` field
+//!   holding their datatype code
+//! * `with = "path"`: decode with `path::decode(r)` and encode with `path::encode(&value, w)`,
+//!   for vendor layouts such as fixed-size ASCII strings
+//! * `optional`: an `Option<T>` field that is `None` when the data ends before it, for fields
+//!   only some devices or versions send. Only trailing fields may be optional, and encoding
+//!   fails if a `None` field is followed by a `Some` one.
+//! * `skip`: not part of the dataset, initialized with `Default::default()`
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument, Ident,
+    LitStr, Path, PathArguments, Type,
+};
+
+#[proc_macro_derive(PtpDataset, attributes(ptp))]
+pub fn derive_ptp_dataset(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    data_type: Option<Ident>,
+    with: Option<Path>,
+    optional: bool,
+    skip: bool,
+}
+
+fn field_attrs(field: &syn::Field) -> Result<FieldAttrs, Error> {
+    let mut attrs = FieldAttrs::default();
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("ptp")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("data_type") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.data_type = Some(lit.parse()?);
+            } else if meta.path.is_ident("with") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.with = Some(lit.parse()?);
+            } else if meta.path.is_ident("optional") {
+                attrs.optional = true;
+            } else if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else {
+                return Err(meta.error("unknown ptp attribute"));
+            }
+            Ok(())
+        })?;
+    }
+    Ok(attrs)
+}
+
+// the last path segment of a type, e.g. `DataType` for `ptp::DataType`
+fn type_name(ty: &Type) -> Option<&Ident> {
+    match ty {
+        Type::Path(p) => p.path.segments.last().map(|s| &s.ident),
+        _ => None,
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(Error::new(
+                    input.span(),
+                    "PtpDataset requires a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "PtpDataset can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut decode = vec![];
+    let mut encode = vec![];
+    let mut names = vec![];
+    let mut min_lens = vec![];
+    let mut optionals = vec![];
+    let mut seen_optional = false;
+
+    for field in fields {
+        let attrs = field_attrs(field)?;
+        let name = field.ident.as_ref().unwrap();
+        let local = format_ident!("__ptp_{}", name);
+        names.push(quote!(#name: #local));
+
+        if attrs.skip {
+            decode.push(quote!(let #local = ::std::default::Default::default();));
+            continue;
+        }
+        if seen_optional && !attrs.optional {
+            return Err(Error::new(
+                field.span(),
+                "fields after an optional field must be optional",
+            ));
+        }
+
+        let ty = if attrs.optional {
+            seen_optional = true;
+            option_inner(&field.ty)
+                .ok_or_else(|| Error::new(field.ty.span(), "optional fields must be Option<T>"))?
+        } else {
+            &field.ty
+        };
+
+        let (read, write) = if let Some(with) = &attrs.with {
+            (quote!(#with::decode(r)), quote!(#with::encode(value, w)?;))
+        } else {
+            match type_name(ty) {
+                Some(ident) if ident == "DataType" || ident == "FormData" => {
+                    let kind = attrs.data_type.as_ref().ok_or_else(|| {
+                        Error::new(
+                            field.span(),
+                            "DataType and FormData fields need #[ptp(data_type = \"field\")]",
+                        )
+                    })?;
+                    let kind = format_ident!("__ptp_{}", kind);
+                    if ident == "DataType" {
+                        (
+                            quote!(::ptp::DataType::read_type(#kind, r)),
+                            quote!(value.encode_into(w)?;),
+                        )
+                    } else {
+                        (
+                            quote!(::ptp::FormData::read_form(#kind, r)),
+                            quote!(value.write_form(w)?;),
+                        )
+                    }
+                }
+                _ => {
+                    if !attrs.optional {
+                        min_lens.push(quote!(<#ty as ::ptp::PtpDecode>::MIN_ENCODED_LEN));
+                    }
+                    (
+                        quote!(<#ty as ::ptp::PtpDecode>::decode_from(r)),
+                        quote!(::ptp::PtpEncode::encode_to(value, w)?;),
+                    )
+                }
+            }
+        };
+
+        if attrs.optional {
+            // the data ended before the field if reading it hit the end before any byte
+            decode.push(quote! {
+                let #local = {
+                    let r = &mut ::ptp::CountingReader::new(&mut *r);
+                    match #read {
+                        Ok(value) => Some(value),
+                        Err(_) if r.count() == 0 && r.at_end() => None,
+                        Err(e) => return Err(e),
+                    }
+                };
+            });
+            optionals.push(quote!(self.#name.is_some()));
+            encode.push(quote! {
+                if let Some(value) = &self.#name {
+                    #write
+                }
+            });
+        } else {
+            decode.push(quote!(let #local = #read?;));
+            encode.push(quote! {
+                {
+                    let value = &self.#name;
+                    #write
+                }
+            });
+        }
+    }
+
+    let ident = &input.ident;
+    // a present field after an absent one would be decoded in its place
+    let check_optionals = if optionals.len() > 1 {
+        let message = format!("{}: optional field set after an absent one", ident);
+        quote! {
+            let present = [#(#optionals),*];
+            if present.windows(2).any(|pair| !pair[0] && pair[1]) {
+                return Err(::ptp::Error::Malformed(#message.to_owned()));
+            }
+        }
+    } else {
+        quote!()
+    };
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::ptp::PtpDecode for #ident #ty_generics #where_clause {
+            const MIN_ENCODED_LEN: usize = 0 #(+ #min_lens)*;
+
+            fn decode_from<R: ::ptp::Read>(r: &mut R) -> ::std::result::Result<Self, ::ptp::Error> {
+                #(#decode)*
+                Ok(#ident { #(#names),* })
+            }
+        }
+
+        impl #impl_generics ::ptp::PtpEncode for #ident #ty_generics #where_clause {
+            fn encode_to<W: ::ptp::Write>(&self, w: &mut W) -> ::std::result::Result<(), ::ptp::Error> {
+                #check_optionals
+                #(#encode)*
+                Ok(())
+            }
+        }
+    })
+}
@@ -1,17 +1,40 @@
 use super::{
-    CommandCode, DeviceInfo, Error, ObjectInfo, Read, StandardCommandCode, StandardResponseCode,
-    StorageInfo,
+    ptpip::{PtpIpOptions, PtpIpResponder, PtpIpTransport},
+    vendor::{self, CodeKind},
+    AccessCapability, CommandCode, DataType, DeviceInfo, Error, ObjectInfo, PropCode, PropInfo,
+    PtpDateTime, PtpEvent, Read, ReadEnd, StandardCommandCode, StandardPropCode,
+    StandardResponseCode, StorageChange, StorageInfo, VendorId,
 };
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use libusb::constants;
-use std::{cmp::min, io::Cursor, slice, time::Duration};
+use std::{
+    cmp::{max, min},
+    io::{self, Cursor},
+    mem,
+    net::ToSocketAddrs,
+    slice,
+    time::{Duration, Instant, SystemTime},
+};
 
 pub struct Camera<'a> {
+    transport: Transport<'a>,
+    current_tid: u32,
+    vendor: Option<VendorId>,
+}
+
+// the connection containers are exchanged over
+enum Transport<'a> {
+    Usb(UsbTransport<'a>),
+    Ip(PtpIpTransport),
+}
+
+struct UsbTransport<'a> {
     iface: u8,
     ep_in: u8,
     ep_out: u8,
-    _ep_int: u8,
-    current_tid: u32,
+    ep_int: u8,
+    // size of event reads, so that no container is cut short
+    int_max_packet: usize,
     handle: libusb::DeviceHandle<'a>,
 }
 
@@ -39,20 +62,60 @@ impl<'a> Camera<'a> {
             interface_desc
                 .endpoint_descriptors()
                 .find(|ep| ep.direction() == direction && ep.transfer_type() == transfer_type)
-                .map(|x| x.address())
                 .ok_or(libusb::Error::NotFound)
         };
+        let interrupt = find_endpoint(libusb::Direction::In, libusb::TransferType::Interrupt)?;
 
         Ok(Camera {
-            iface: interface_desc.interface_number(),
-            ep_in: find_endpoint(libusb::Direction::In, libusb::TransferType::Bulk)?,
-            ep_out: find_endpoint(libusb::Direction::Out, libusb::TransferType::Bulk)?,
-            _ep_int: find_endpoint(libusb::Direction::In, libusb::TransferType::Interrupt)?,
+            transport: Transport::Usb(UsbTransport {
+                iface: interface_desc.interface_number(),
+                ep_in: find_endpoint(libusb::Direction::In, libusb::TransferType::Bulk)?.address(),
+                ep_out: find_endpoint(libusb::Direction::Out, libusb::TransferType::Bulk)?
+                    .address(),
+                ep_int: interrupt.address(),
+                int_max_packet: usize::from(interrupt.max_packet_size()),
+                handle,
+            }),
             current_tid: 0,
-            handle,
+            vendor: None,
         })
     }
 
+    /// Connect to a PTP/IP responder, such as a camera on Wi-Fi.
+    ///
+    /// `options.handshake` selects the standard handshake or a vendor variant. A session
+    /// must then be opened as for USB devices.
+    pub fn connect_ip<A: ToSocketAddrs>(
+        addr: A,
+        options: &PtpIpOptions,
+        timeout: Option<Duration>,
+    ) -> Result<Camera<'a>, Error> {
+        let transport = PtpIpTransport::connect(addr, options, timeout.unwrap_or_default())?;
+        Ok(Camera {
+            transport: Transport::Ip(transport),
+            current_tid: 0,
+            vendor: None,
+        })
+    }
+
+    /// The responder's half of the PTP/IP handshake, for connections that have one
+    pub fn ptpip_responder(&self) -> Option<&PtpIpResponder> {
+        match self.transport {
+            Transport::Ip(ref ip) => ip.responder(),
+            Transport::Usb(_) => None,
+        }
+    }
+
+    fn usb(&mut self, operation: &str) -> Result<&mut UsbTransport<'a>, Error> {
+        match self.transport {
+            Transport::Usb(ref mut usb) => Ok(usb),
+            Transport::Ip(_) => Err(Error::Unsupported(format!(
+                "{} on a PTP/IP connection",
+                operation
+            ))),
+        }
+    }
+
     /// execute a PTP transaction.
     /// consists of the following phases:
     ///  - command
@@ -80,17 +143,35 @@ impl<'a> Camera<'a> {
             request_payload.write_u32::<LittleEndian>(*p).ok();
         }
 
-        self.write_txn_phase(ContainerType::Command, code, tid, &request_payload, timeout)?;
+        trace!(
+            "Write 0x{:04x} ({}), tid:{}{}",
+            code,
+            vendor::code_name(self.vendor, CodeKind::Command, code).unwrap_or("unknown"),
+            tid,
+            if data.is_some() { " with data" } else { "" }
+        );
 
-        if let Some(data) = data {
-            self.write_txn_phase(ContainerType::Data, code, tid, data, timeout)?;
+        match self.transport {
+            Transport::Usb(ref mut usb) => {
+                usb.write_txn_phase(ContainerType::Command, code, tid, &request_payload, timeout)?;
+                if let Some(data) = data {
+                    usb.write_txn_phase(ContainerType::Data, code, tid, data, timeout)?;
+                }
+            }
+            Transport::Ip(ref mut ip) => {
+                ip.write_request(code, tid, &request_payload, data, timeout)?
+            }
         }
 
         // request phase is followed by data phase (optional) and response phase.
         // read both, check the status on the response, and return the data payload, if any.
         let mut data_phase_payload = vec![];
         loop {
-            let (container, payload) = self.read_txn_phase(timeout)?;
+            let (container, payload) = match self.transport {
+                Transport::Usb(ref mut usb) => usb.read_txn_phase(timeout)?,
+                Transport::Ip(ref mut ip) => ip.read_phase(timeout)?,
+            };
+            trace!("container {:?}", container);
             if !container.belongs_to(tid) {
                 return Err(Error::Malformed(format!(
                     "mismatched txnid {}, expecting {}",
@@ -112,89 +193,35 @@ impl<'a> Camera<'a> {
         }
     }
 
-    fn write_txn_phase(
-        &mut self,
-        kind: ContainerType,
-        code: CommandCode,
-        tid: u32,
-        payload: &[u8],
-        timeout: Duration,
-    ) -> Result<(), Error> {
-        trace!(
-            "Write {:?} - 0x{:04x} ({}), tid:{}",
-            kind,
-            code,
-            StandardCommandCode::name(code).unwrap_or("unknown"),
-            tid
-        );
-
-        const CHUNK_SIZE: usize = 1024 * 1024; // 1MB, must be a multiple of the endpoint packet size
-
-        // The first chunk contains the header, and its payload must be copied into the temporary buffer
-        let first_chunk_payload_bytes = min(payload.len(), CHUNK_SIZE - CONTAINER_INFO_SIZE);
-        let mut buf = Vec::with_capacity(first_chunk_payload_bytes + CONTAINER_INFO_SIZE);
-        buf.write_u32::<LittleEndian>((payload.len() + CONTAINER_INFO_SIZE) as u32)
-            .ok();
-        buf.write_u16::<LittleEndian>(kind as u16).ok();
-        buf.write_u16::<LittleEndian>(code).ok();
-        buf.write_u32::<LittleEndian>(tid).ok();
-        buf.extend_from_slice(&payload[..first_chunk_payload_bytes]);
-        self.handle.write_bulk(self.ep_out, &buf, timeout)?;
-
-        // Write any subsequent chunks, straight from the source slice
-        for chunk in payload[first_chunk_payload_bytes..].chunks(CHUNK_SIZE) {
-            self.handle.write_bulk(self.ep_out, chunk, timeout)?;
-        }
-
-        Ok(())
+    /// The vendor extension used to name vendor codes in traces.
+    /// Detected by `get_device_info`, or `None` before it has been called.
+    pub fn vendor(&self) -> Option<VendorId> {
+        self.vendor
     }
 
-    // helper for command() above, retrieve container info and payload for the current phase
-    fn read_txn_phase(&mut self, timeout: Duration) -> Result<(ContainerInfo, Vec<u8>), Error> {
-        // buf is stack allocated and intended to be large enough to accomodate most
-        // cmd/ctrl data (ie, not media) without allocating. payload handling below
-        // deals with larger media responses. mark it as uninitalized to avoid paying
-        // for zeroing out 8k of memory, since rust doesn't know what libusb does with this memory.
-        let mut unintialized_buf: [u8; 8 * 1024];
-        let buf = unsafe {
-            unintialized_buf = ::std::mem::uninitialized();
-            let n = self
-                .handle
-                .read_bulk(self.ep_in, &mut unintialized_buf[..], timeout)?;
-            &unintialized_buf[..n]
-        };
-
-        let cinfo = ContainerInfo::parse(&buf[..])?;
-        trace!("container {:?}", cinfo);
-
-        // no payload? we're done
-        if cinfo.payload_len == 0 {
-            return Ok((cinfo, vec![]));
-        }
+    /// Override the detected vendor, e.g. for devices that misreport their extension set.
+    pub fn set_vendor(&mut self, vendor: Option<VendorId>) {
+        self.vendor = vendor;
+    }
 
-        // allocate one extra to avoid a separate read for trailing short packet
-        let mut payload = Vec::with_capacity(cinfo.payload_len + 1);
-        payload.extend_from_slice(&buf[CONTAINER_INFO_SIZE..]);
+    /// Wait for an event on the interrupt endpoint, or the event connection of PTP/IP devices.
+    /// Returns `None` if no event arrived within `timeout`.
+    pub fn event(&mut self, timeout: Option<Duration>) -> Result<Option<PtpEvent>, Error> {
+        let timeout = timeout.unwrap_or_default();
 
-        // response didn't fit into our original buf? read the rest
-        // or if our original read were satisfied exactly, so there is still a ZLP to read
-        if payload.len() < cinfo.payload_len || buf.len() == unintialized_buf.len() {
-            unsafe {
-                let p = payload.as_mut_ptr().add(payload.len());
-                let pslice = slice::from_raw_parts_mut(p, payload.capacity() - payload.len());
-                let n = self.handle.read_bulk(self.ep_in, pslice, timeout)?;
-                let sz = payload.len();
-                payload.set_len(sz + n);
-                trace!(
-                    "  bulk rx {}, ({}/{})",
-                    n,
-                    payload.len(),
-                    payload.capacity()
-                );
-            }
+        let event = match self.transport {
+            Transport::Usb(ref mut usb) => usb.read_event(timeout)?,
+            Transport::Ip(ref mut ip) => ip.read_event(timeout)?,
+        };
+        if let Some(ref event) = event {
+            trace!(
+                "Event 0x{:04x} ({}) {:?}",
+                event.code(),
+                vendor::code_name(self.vendor, CodeKind::Event, event.code()).unwrap_or("unknown"),
+                event
+            );
         }
-
-        Ok((cinfo, payload))
+        Ok(event)
     }
 
     pub fn get_objectinfo(
@@ -235,6 +262,122 @@ impl<'a> Camera<'a> {
             .map(|_| ())
     }
 
+    /// Take a picture and wait for the objects it creates.
+    ///
+    /// `storage_id` and `format` may be 0 to let the device choose. Collects the
+    /// `ObjectAdded` events of the capture until `CaptureComplete`, so RAW+JPEG pairs and
+    /// bursts yield every object. Devices that omit `CaptureComplete` for single shots are
+    /// handled by stopping once no object was added for two seconds. The first object is
+    /// waited for up to `timeout`.
+    ///
+    /// Fails with `Error::StoreFull` if the device runs out of space.
+    pub fn capture(
+        &mut self,
+        storage_id: u32,
+        format: u16,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<(u32, ObjectInfo)>, Error> {
+        let tid = self.current_tid;
+        self.command(
+            StandardCommandCode::InitiateCapture,
+            &[storage_id, u32::from(format)],
+            None,
+            timeout,
+        )?;
+
+        let handles = self.wait_capture_objects(tid, timeout)?;
+        handles
+            .into_iter()
+            .map(|handle| Ok((handle, self.get_objectinfo(handle, timeout)?)))
+            .collect()
+    }
+
+    /// `capture`, then download the new objects, deleting them from the device if `delete` is set.
+    pub fn capture_and_download(
+        &mut self,
+        storage_id: u32,
+        format: u16,
+        delete: bool,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<(ObjectInfo, Vec<u8>)>, Error> {
+        let objects = self.capture(storage_id, format, timeout)?;
+        objects
+            .into_iter()
+            .map(|(handle, info)| {
+                let data = self.get_object(handle, timeout)?;
+                if delete {
+                    self.delete_object(handle, timeout)?;
+                }
+                Ok((info, data))
+            })
+            .collect()
+    }
+
+    /// Start an open-ended capture, such as a bulb exposure or a video recording.
+    ///
+    /// The capture runs until the returned guard is stopped or dropped.
+    pub fn open_capture<'c>(
+        &'c mut self,
+        storage_id: u32,
+        format: u16,
+        timeout: Option<Duration>,
+    ) -> Result<OpenCapture<'c, 'a>, Error> {
+        let tid = self.current_tid;
+        self.command(
+            StandardCommandCode::InitiateOpenCapture,
+            &[storage_id, u32::from(format)],
+            None,
+            timeout,
+        )?;
+
+        Ok(OpenCapture {
+            camera: self,
+            tid,
+            timeout,
+            handles: vec![],
+            complete: false,
+            terminated: false,
+        })
+    }
+
+    // collect the handles added by the capture started in transaction `tid`
+    fn wait_capture_objects(
+        &mut self,
+        tid: u32,
+        timeout: Option<Duration>,
+    ) -> Result<Vec<u32>, Error> {
+        let mut handles = vec![];
+        // for devices without CaptureComplete, the capture ends once objects stop arriving
+        let mut settled_at = None;
+        loop {
+            let wait = match settled_at {
+                Some(deadline) => match settle_wait(deadline) {
+                    Some(wait) => Some(wait),
+                    None => return Ok(handles),
+                },
+                None => timeout,
+            };
+            match self.event(wait)? {
+                Some(event) => {
+                    let added = handles.len();
+                    if capture_event(tid, event, &mut handles)? {
+                        return Ok(handles);
+                    }
+                    if handles.len() > added {
+                        settled_at = Some(Instant::now() + CAPTURE_SETTLE_TIME);
+                    }
+                }
+                None if settled_at.is_some() => return Ok(handles),
+                None => {
+                    return Err(Error::Io(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "timed out waiting for captured objects",
+                    )))
+                }
+            }
+        }
+    }
+
     pub fn get_objecthandles(
         &mut self,
         storage_id: u32,
@@ -317,6 +460,74 @@ impl<'a> Camera<'a> {
         Ok(res)
     }
 
+    /// Erase a store, formatting it with `filesystem_format` (0 lets the device choose).
+    ///
+    /// As a safeguard, `confirm` must equal the `VolumeLabel` of the store, or its
+    /// `StorageDescription` if it has no label, and the store must be writable.
+    pub fn format_store(
+        &mut self,
+        storage_id: u32,
+        filesystem_format: u16,
+        confirm: &str,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        let info = self.get_storage_info(storage_id, timeout)?;
+
+        let expected = if info.VolumeLabel.is_empty() {
+            &info.StorageDescription
+        } else {
+            &info.VolumeLabel
+        };
+        if expected.is_empty() || confirm != expected {
+            return Err(Error::Refused(format!(
+                "confirmation {:?} does not match store 0x{:08x} ({:?})",
+                confirm, storage_id, expected
+            )));
+        }
+        if info.access_capability() != AccessCapability::ReadWrite {
+            return Err(Error::Refused(format!(
+                "store 0x{:08x} is read-only ({:?})",
+                storage_id,
+                info.access_capability()
+            )));
+        }
+
+        self.command(
+            StandardCommandCode::FormatStore,
+            &[storage_id, u32::from(filesystem_format)],
+            None,
+            timeout,
+        )
+        .map(|_| ())
+    }
+
+    /// Wait for a store to be added, removed or changed, fetching its new `StorageInfo`.
+    /// Other events are discarded. Returns `None` if no event arrived within `timeout`.
+    pub fn wait_storage_change(
+        &mut self,
+        timeout: Option<Duration>,
+    ) -> Result<Option<StorageChange>, Error> {
+        while let Some(event) = self.event(timeout)? {
+            let change = match event {
+                PtpEvent::StoreAdded { storage_id } => StorageChange::Added {
+                    storage_id,
+                    info: self.get_storage_info(storage_id, timeout)?,
+                },
+                PtpEvent::StoreRemoved { storage_id } => StorageChange::Removed { storage_id },
+                PtpEvent::StorageInfoChanged { storage_id } => StorageChange::InfoChanged {
+                    storage_id,
+                    info: self.get_storage_info(storage_id, timeout)?,
+                },
+                event => {
+                    debug!("ignoring event while watching stores: {:?}", event);
+                    continue;
+                }
+            };
+            return Ok(Some(change));
+        }
+        Ok(None)
+    }
+
     pub fn get_storageids(&mut self, timeout: Option<Duration>) -> Result<Vec<u32>, Error> {
         let data = self.command(StandardCommandCode::GetStorageIDs, &[], None, timeout)?;
 
@@ -356,9 +567,99 @@ impl<'a> Camera<'a> {
 
         let device_info = DeviceInfo::decode(&data)?;
         debug!("device_info {:?}", device_info);
+        self.vendor = Some(device_info.vendor());
         Ok(device_info)
     }
 
+    pub fn get_device_prop_desc(
+        &mut self,
+        prop: PropCode,
+        timeout: Option<Duration>,
+    ) -> Result<PropInfo, Error> {
+        let data = self.command(
+            StandardCommandCode::GetDevicePropDesc,
+            &[u32::from(prop)],
+            None,
+            timeout,
+        )?;
+
+        let mut cur = Cursor::new(data);
+        let res = PropInfo::decode(&mut cur)?;
+        cur.expect_end()?;
+
+        Ok(res)
+    }
+
+    // data_type: the DataType code of the property, as reported by its PropInfo
+    pub fn get_device_prop_value(
+        &mut self,
+        prop: PropCode,
+        data_type: u16,
+        timeout: Option<Duration>,
+    ) -> Result<DataType, Error> {
+        let data = self.command(
+            StandardCommandCode::GetDevicePropValue,
+            &[u32::from(prop)],
+            None,
+            timeout,
+        )?;
+
+        let mut cur = Cursor::new(data);
+        let value = DataType::read_type(data_type, &mut cur)?;
+        cur.expect_end()?;
+
+        Ok(value)
+    }
+
+    pub fn set_device_prop_value(
+        &mut self,
+        prop: PropCode,
+        value: &DataType,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.command(
+            StandardCommandCode::SetDevicePropValue,
+            &[u32::from(prop)],
+            Some(&value.encode()?),
+            timeout,
+        )
+        .map(|_| ())
+    }
+
+    /// Read the `DateTime` device property.
+    pub fn get_date_time(&mut self, timeout: Option<Duration>) -> Result<PtpDateTime, Error> {
+        match self.get_device_prop_value(StandardPropCode::DateTime, 0xFFFF, timeout)? {
+            DataType::STR(s) => PtpDateTime::parse(&s),
+            v => Err(Error::Malformed(format!(
+                "DateTime of unexpected type {:?}",
+                v
+            ))),
+        }
+    }
+
+    /// Set the `DateTime` device property.
+    pub fn set_date_time(
+        &mut self,
+        date_time: &PtpDateTime,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        self.set_device_prop_value(
+            StandardPropCode::DateTime,
+            &DataType::STR(date_time.to_string()),
+            timeout,
+        )
+    }
+
+    /// Set the device clock from the host clock, as the local time at `utc_offset` minutes
+    /// from UTC.
+    ///
+    /// Most devices ignore the zone designator and show the time as sent, so pass the offset
+    /// of the local time zone for the camera to show local time, or 0 for UTC.
+    pub fn sync_clock(&mut self, utc_offset: i16, timeout: Option<Duration>) -> Result<(), Error> {
+        let now = PtpDateTime::from_system_time_with_offset(SystemTime::now(), utc_offset)?;
+        self.set_date_time(&now, timeout)
+    }
+
     pub fn open_session(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
         let session_id = 1;
 
@@ -380,25 +681,251 @@ impl<'a> Camera<'a> {
 
     pub fn disconnect(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
         self.close_session(timeout)?;
-        self.handle.release_interface(self.iface)?;
+        match self.transport {
+            Transport::Usb(ref mut usb) => usb.handle.release_interface(usb.iface)?,
+            Transport::Ip(ref mut ip) => ip.disconnect()?,
+        }
         Ok(())
     }
 
+    /// Reset the USB device. Not available over PTP/IP.
     pub fn reset(&mut self) -> Result<(), Error> {
-        self.handle.reset()?;
+        self.usb("reset")?.handle.reset()?;
         Ok(())
     }
 
+    /// Clear a halt on the bulk endpoints. Not available over PTP/IP.
     pub fn clear_halt(&mut self) -> Result<(), Error> {
-        self.handle.clear_halt(self.ep_in)?;
-        self.handle.clear_halt(self.ep_out)?;
+        let usb = self.usb("clear halt")?;
+        usb.handle.clear_halt(usb.ep_in)?;
+        usb.handle.clear_halt(usb.ep_out)?;
         Ok(())
     }
 }
 
+impl<'a> UsbTransport<'a> {
+    fn write_txn_phase(
+        &mut self,
+        kind: ContainerType,
+        code: CommandCode,
+        tid: u32,
+        payload: &[u8],
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        const CHUNK_SIZE: usize = 1024 * 1024; // 1MB, must be a multiple of the endpoint packet size
+
+        // The first chunk contains the header, and its payload must be copied into the temporary buffer
+        let first_chunk_payload_bytes = min(payload.len(), CHUNK_SIZE - CONTAINER_INFO_SIZE);
+        let mut buf = Vec::with_capacity(first_chunk_payload_bytes + CONTAINER_INFO_SIZE);
+        buf.write_u32::<LittleEndian>((payload.len() + CONTAINER_INFO_SIZE) as u32)
+            .ok();
+        buf.write_u16::<LittleEndian>(kind as u16).ok();
+        buf.write_u16::<LittleEndian>(code).ok();
+        buf.write_u32::<LittleEndian>(tid).ok();
+        buf.extend_from_slice(&payload[..first_chunk_payload_bytes]);
+        self.handle.write_bulk(self.ep_out, &buf, timeout)?;
+
+        // Write any subsequent chunks, straight from the source slice
+        for chunk in payload[first_chunk_payload_bytes..].chunks(CHUNK_SIZE) {
+            self.handle.write_bulk(self.ep_out, chunk, timeout)?;
+        }
+
+        Ok(())
+    }
+
+    // helper for command() above, retrieve container info and payload for the current phase
+    fn read_txn_phase(&mut self, timeout: Duration) -> Result<(ContainerInfo, Vec<u8>), Error> {
+        // buf is stack allocated and intended to be large enough to accomodate most
+        // cmd/ctrl data (ie, not media) without allocating. payload handling below
+        // deals with larger media responses. mark it as uninitalized to avoid paying
+        // for zeroing out 8k of memory, since rust doesn't know what libusb does with this memory.
+        let mut unintialized_buf: [u8; 8 * 1024];
+        let buf = unsafe {
+            unintialized_buf = ::std::mem::uninitialized();
+            let n = self
+                .handle
+                .read_bulk(self.ep_in, &mut unintialized_buf[..], timeout)?;
+            &unintialized_buf[..n]
+        };
+
+        let cinfo = ContainerInfo::parse(&buf[..])?;
+
+        // no payload? we're done
+        if cinfo.payload_len == 0 {
+            return Ok((cinfo, vec![]));
+        }
+
+        // allocate one extra to avoid a separate read for trailing short packet
+        let mut payload = Vec::with_capacity(cinfo.payload_len + 1);
+        payload.extend_from_slice(&buf[CONTAINER_INFO_SIZE..]);
+
+        // response didn't fit into our original buf? read the rest
+        // or if our original read were satisfied exactly, so there is still a ZLP to read
+        if payload.len() < cinfo.payload_len || buf.len() == unintialized_buf.len() {
+            unsafe {
+                let p = payload.as_mut_ptr().add(payload.len());
+                let pslice = slice::from_raw_parts_mut(p, payload.capacity() - payload.len());
+                let n = self.handle.read_bulk(self.ep_in, pslice, timeout)?;
+                let sz = payload.len();
+                payload.set_len(sz + n);
+                trace!(
+                    "  bulk rx {}, ({}/{})",
+                    n,
+                    payload.len(),
+                    payload.capacity()
+                );
+            }
+        }
+
+        Ok((cinfo, payload))
+    }
+
+    fn read_event(&mut self, timeout: Duration) -> Result<Option<PtpEvent>, Error> {
+        // standard events carry at most three parameters, vendor ones may carry more
+        let mut buf = vec![0u8; max(self.int_max_packet, CONTAINER_INFO_SIZE + 3 * 4)];
+        let n = match self.handle.read_interrupt(self.ep_int, &mut buf, timeout) {
+            Ok(n) => n,
+            Err(libusb::Error::Timeout) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(PtpEvent::decode(&buf[..n])?))
+    }
+}
+
+// how long a capture keeps waiting for objects after the last one was added
+const CAPTURE_SETTLE_TIME: Duration = Duration::from_secs(2);
+
+// time left until `deadline`, or `None` once it passed. Never zero, which waits forever.
+fn settle_wait(deadline: Instant) -> Option<Duration> {
+    let now = Instant::now();
+    if now >= deadline {
+        None
+    } else {
+        Some(max(deadline - now, Duration::from_millis(1)))
+    }
+}
+
+// track an event of the capture started in transaction `tid`, returning whether it completed
+fn capture_event(tid: u32, event: PtpEvent, handles: &mut Vec<u32>) -> Result<bool, Error> {
+    match event {
+        PtpEvent::ObjectAdded { handle } => handles.push(handle),
+        PtpEvent::CaptureComplete { transaction_id } if transaction_id == tid => return Ok(true),
+        PtpEvent::StoreFull { storage_id } => return Err(Error::StoreFull(storage_id)),
+        PtpEvent::CancelTransaction { transaction_id } if transaction_id == tid => {
+            return Err(Error::Response(StandardResponseCode::TransactionCancelled));
+        }
+        event => debug!("ignoring event during capture: {:?}", event),
+    }
+    Ok(false)
+}
+
+/// A running open capture, terminated when dropped
+pub struct OpenCapture<'c, 'a> {
+    camera: &'c mut Camera<'a>,
+    tid: u32,
+    timeout: Option<Duration>,
+    handles: Vec<u32>,
+    complete: bool,
+    terminated: bool,
+}
+
+impl<'c, 'a> OpenCapture<'c, 'a> {
+    /// Transaction ID of the `InitiateOpenCapture` operation
+    pub fn transaction_id(&self) -> u32 {
+        self.tid
+    }
+
+    /// Handles of the objects added so far
+    pub fn handles(&self) -> &[u32] {
+        &self.handles
+    }
+
+    /// Whether the device ended the capture on its own, e.g. at the end of a timed exposure
+    pub fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    /// Process the events received within `duration`, returning the handles of newly added objects.
+    pub fn poll(&mut self, duration: Duration) -> Result<&[u32], Error> {
+        let start = self.handles.len();
+        let deadline = Instant::now() + duration;
+        while !self.complete {
+            let wait = match settle_wait(deadline) {
+                Some(wait) => wait,
+                None => break,
+            };
+            if let Some(event) = self.camera.event(Some(wait))? {
+                self.complete = capture_event(self.tid, event, &mut self.handles)?;
+            }
+        }
+        Ok(&self.handles[start..])
+    }
+
+    /// Terminate the capture after `duration`, then wait for its objects.
+    pub fn stop_after(mut self, duration: Duration) -> Result<Vec<(u32, ObjectInfo)>, Error> {
+        self.poll(duration)?;
+        self.stop()
+    }
+
+    /// Terminate the capture and wait for the objects it created.
+    ///
+    /// Not every device reports `CaptureComplete` after `TerminateOpenCapture`, so objects are
+    /// collected until no further one is added for two seconds.
+    pub fn stop(mut self) -> Result<Vec<(u32, ObjectInfo)>, Error> {
+        if !self.complete {
+            self.terminate()?;
+            let mut settled_at = Instant::now() + CAPTURE_SETTLE_TIME;
+            while let Some(wait) = settle_wait(settled_at) {
+                let event = match self.camera.event(Some(wait))? {
+                    Some(event) => event,
+                    None => break,
+                };
+                let added = self.handles.len();
+                if capture_event(self.tid, event, &mut self.handles)? {
+                    break;
+                }
+                if self.handles.len() > added {
+                    settled_at = Instant::now() + CAPTURE_SETTLE_TIME;
+                }
+            }
+        }
+
+        let handles = mem::take(&mut self.handles);
+        let timeout = self.timeout;
+        handles
+            .into_iter()
+            .map(|handle| Ok((handle, self.camera.get_objectinfo(handle, timeout)?)))
+            .collect()
+    }
+
+    fn terminate(&mut self) -> Result<(), Error> {
+        self.terminated = true;
+        self.camera
+            .command(
+                StandardCommandCode::TerminateOpenCapture,
+                &[self.tid],
+                None,
+                self.timeout,
+            )
+            .map(|_| ())
+    }
+}
+
+impl<'c, 'a> Drop for OpenCapture<'c, 'a> {
+    fn drop(&mut self) {
+        if self.terminated || self.complete {
+            return;
+        }
+        if let Err(e) = self.terminate() {
+            warn!("failed to terminate open capture: {}", e);
+        }
+    }
+}
+
 #[derive(Debug, PartialEq)]
 #[repr(u16)]
-enum ContainerType {
+pub enum ContainerType {
     Command = 1,
     Data = 2,
     Response = 3,
@@ -419,21 +946,21 @@ impl ContainerType {
 }
 
 #[derive(Debug)]
-struct ContainerInfo {
+pub struct ContainerInfo {
     /// payload len in bytes, usually relevant for data phases
-    payload_len: usize,
+    pub payload_len: usize,
 
     /// Container kind
-    kind: ContainerType,
+    pub kind: ContainerType,
 
-    /// StandardCommandCode or ResponseCode, depending on 'kind'
-    code: u16,
+    /// StandardCommandCode, ResponseCode or EventCode, depending on 'kind'
+    pub code: u16,
 
     /// transaction ID that this container belongs to
-    tid: u32,
+    pub tid: u32,
 }
 
-const CONTAINER_INFO_SIZE: usize = 12;
+pub(crate) const CONTAINER_INFO_SIZE: usize = 12;
 
 impl ContainerInfo {
     pub fn parse<R: ReadBytesExt>(mut r: R) -> Result<ContainerInfo, Error> {
@@ -443,9 +970,17 @@ impl ContainerInfo {
             .ok_or_else(|| Error::Malformed(format!("Invalid message type {:x}.", kind_u16)))?;
         let code = r.read_u16::<LittleEndian>()?;
         let tid = r.read_u32::<LittleEndian>()?;
+        let payload_len = (len as usize)
+            .checked_sub(CONTAINER_INFO_SIZE)
+            .ok_or_else(|| {
+                Error::Malformed(format!(
+                    "Container length {} is shorter than its header",
+                    len
+                ))
+            })?;
 
         Ok(ContainerInfo {
-            payload_len: len as usize - CONTAINER_INFO_SIZE,
+            payload_len,
             kind,
             tid,
             code,
@@ -1,9 +1,19 @@
-use super::{Error, Read};
-use byteorder::{LittleEndian, WriteBytesExt};
-use std::io::Write;
+use super::{read::MAX_PREALLOC_LEN, Error, Read, Write};
+use std::{
+    cmp::{max, min},
+    convert::TryFrom,
+};
 
+/// A value of one of the PTP datatypes.
+///
+/// With the `serde` feature, values are represented as the variant name and the value, e.g.
+/// `{"type": "UINT16", "value": 100}`, `{"type": "AUINT8", "value": [1, 2]}` or `{"type": "UNDEF"}`.
+/// 128-bit values are decimal strings, e.g. `{"type": "INT128", "value": "-5"}`, as most formats
+/// have no 128-bit integers.
 #[allow(non_snake_case)]
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", content = "value"))]
 pub enum DataType {
     UNDEF,
     INT8(i8),
@@ -14,8 +24,8 @@ pub enum DataType {
     UINT32(u32),
     INT64(i64),
     UINT64(u64),
-    INT128(i128),
-    UINT128(u128),
+    INT128(#[cfg_attr(feature = "serde", serde(with = "decimal"))] i128),
+    UINT128(#[cfg_attr(feature = "serde", serde(with = "decimal"))] u128),
     AINT8(Vec<i8>),
     AUINT8(Vec<u8>),
     AINT16(Vec<i16>),
@@ -24,125 +34,85 @@ pub enum DataType {
     AUINT32(Vec<u32>),
     AINT64(Vec<i64>),
     AUINT64(Vec<u64>),
-    AINT128(Vec<i128>),
-    AUINT128(Vec<u128>),
+    AINT128(#[cfg_attr(feature = "serde", serde(with = "decimal::vec"))] Vec<i128>),
+    AUINT128(#[cfg_attr(feature = "serde", serde(with = "decimal::vec"))] Vec<u128>),
     STR(String),
 }
 
 impl DataType {
-    pub fn encode(&self) -> Vec<u8> {
-        use self::DataType::*;
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
         let mut out = vec![];
+        self.encode_into(&mut out)?;
+        Ok(out)
+    }
+
+    /// Write the value without allocating. Fails for `UNDEF` and strings over 254 UTF-16 code units.
+    pub fn encode_into<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        use self::DataType::*;
         match self {
-            // UNDEF => {},
-            INT8(val) => {
-                out.write_i8(*val).ok();
-            }
-            UINT8(val) => {
-                out.write_u8(*val).ok();
-            }
-            INT16(val) => {
-                out.write_i16::<LittleEndian>(*val).ok();
-            }
-            UINT16(val) => {
-                out.write_u16::<LittleEndian>(*val).ok();
-            }
-            INT32(val) => {
-                out.write_i32::<LittleEndian>(*val).ok();
-            }
-            UINT32(val) => {
-                out.write_u32::<LittleEndian>(*val).ok();
-            }
-            INT64(val) => {
-                out.write_i64::<LittleEndian>(*val).ok();
-            }
-            UINT64(val) => {
-                out.write_u64::<LittleEndian>(*val).ok();
-            }
-            INT128(val) => {
-                out.write_i128::<LittleEndian>(*val).ok();
-            }
-            UINT128(val) => {
-                out.write_u128::<LittleEndian>(*val).ok();
-            }
-            AINT8(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_i8(*item).ok();
-                }
-            }
-            AUINT8(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_u8(*item).ok();
-                }
-            }
-            AINT16(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_i16::<LittleEndian>(*item).ok();
-                }
-            }
-            AUINT16(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_u16::<LittleEndian>(*item).ok();
-                }
-            }
-            AINT32(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_i32::<LittleEndian>(*item).ok();
-                }
-            }
-            AUINT32(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_u32::<LittleEndian>(*item).ok();
-                }
-            }
-            AINT64(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_i64::<LittleEndian>(*item).ok();
-                }
-            }
-            AUINT64(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_u64::<LittleEndian>(*item).ok();
-                }
-            }
-            AINT128(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_i128::<LittleEndian>(*item).ok();
-                }
-            }
-            AUINT128(val) => {
-                out.write_u32::<LittleEndian>(val.len() as u32).ok();
-                for item in val {
-                    out.write_u128::<LittleEndian>(*item).ok();
-                }
-            }
-            STR(val) => {
-                out.write_u8(((val.len() as u8) * 2) + 1).ok();
-                if !val.is_empty() {
-                    for e in val.encode_utf16() {
-                        out.write_u16::<LittleEndian>(e).ok();
-                    }
-                    out.write_all(b"\0\0").ok();
-                }
-            }
-            _ => {}
+            UNDEF => Err(Error::Malformed(
+                "Cannot encode a value of undefined type".to_string(),
+            )),
+            INT8(val) => w.write_ptp_i8(*val),
+            UINT8(val) => w.write_ptp_u8(*val),
+            INT16(val) => w.write_ptp_i16(*val),
+            UINT16(val) => w.write_ptp_u16(*val),
+            INT32(val) => w.write_ptp_i32(*val),
+            UINT32(val) => w.write_ptp_u32(*val),
+            INT64(val) => w.write_ptp_i64(*val),
+            UINT64(val) => w.write_ptp_u64(*val),
+            INT128(val) => w.write_ptp_i128(*val),
+            UINT128(val) => w.write_ptp_u128(*val),
+            AINT8(val) => w.write_ptp_i8_vec(val),
+            AUINT8(val) => w.write_ptp_u8_vec(val),
+            AINT16(val) => w.write_ptp_i16_vec(val),
+            AUINT16(val) => w.write_ptp_u16_vec(val),
+            AINT32(val) => w.write_ptp_i32_vec(val),
+            AUINT32(val) => w.write_ptp_u32_vec(val),
+            AINT64(val) => w.write_ptp_i64_vec(val),
+            AUINT64(val) => w.write_ptp_u64_vec(val),
+            AINT128(val) => w.write_ptp_i128_vec(val),
+            AUINT128(val) => w.write_ptp_u128_vec(val),
+            STR(val) => w.write_ptp_str(val),
+        }
+    }
+
+    /// The datatype code of the value, as used in `PropInfo::data_type`
+    pub fn type_code(&self) -> u16 {
+        use self::DataType::*;
+        match self {
+            UNDEF => 0x0000,
+            INT8(_) => 0x0001,
+            UINT8(_) => 0x0002,
+            INT16(_) => 0x0003,
+            UINT16(_) => 0x0004,
+            INT32(_) => 0x0005,
+            UINT32(_) => 0x0006,
+            INT64(_) => 0x0007,
+            UINT64(_) => 0x0008,
+            INT128(_) => 0x0009,
+            UINT128(_) => 0x000A,
+            AINT8(_) => 0x4001,
+            AUINT8(_) => 0x4002,
+            AINT16(_) => 0x4003,
+            AUINT16(_) => 0x4004,
+            AINT32(_) => 0x4005,
+            AUINT32(_) => 0x4006,
+            AINT64(_) => 0x4007,
+            AUINT64(_) => 0x4008,
+            AINT128(_) => 0x4009,
+            AUINT128(_) => 0x400A,
+            STR(_) => 0xFFFF,
         }
-        out
     }
 
+    /// Read a value of datatype `kind`. Unknown datatypes are `Error::Malformed`: their size is
+    /// unknown, so nothing after the value, such as the rest of a `PropInfo`, could be located.
+    /// So is `UNDEF` (0x0000), which has no encoding. See `read_type_lenient` for callers that
+    /// can do without the value.
     pub fn read_type<T: Read>(kind: u16, reader: &mut T) -> Result<DataType, Error> {
         use self::DataType::*;
         Ok(match kind {
-            // 0x0000 => UNDEF,
             0x0001 => INT8(reader.read_ptp_i8()?),
             0x0002 => UINT8(reader.read_ptp_u8()?),
             0x0003 => INT16(reader.read_ptp_i16()?),
@@ -164,9 +134,42 @@ impl DataType {
             0x4009 => AINT128(reader.read_ptp_i128_vec()?),
             0x400A => AUINT128(reader.read_ptp_u128_vec()?),
             0xFFFF => STR(reader.read_ptp_str()?),
-            _ => UNDEF,
+            _ => {
+                return Err(Error::Malformed(format!(
+                    "Unknown data type 0x{:04x}",
+                    kind
+                )))
+            }
         })
     }
+
+    /// Like `read_type`, but yields `UNDEF` for unknown type codes without consuming any data,
+    /// for callers that read nothing after the value.
+    pub fn read_type_lenient<T: Read>(kind: u16, reader: &mut T) -> Result<DataType, Error> {
+        if DataType::is_known_type(kind) {
+            DataType::read_type(kind, reader)
+        } else {
+            Ok(DataType::UNDEF)
+        }
+    }
+
+    pub(crate) fn is_known_type(kind: u16) -> bool {
+        matches!(kind, 0x0001..=0x000A | 0x4001..=0x400A | 0xFFFF)
+    }
+
+    // smallest encoding of a value of type `kind`: its size for scalars, the count for arrays
+    fn min_encoded_len(kind: u16) -> usize {
+        match kind {
+            0x0001 | 0x0002 => 1,
+            0x0003 | 0x0004 => 2,
+            0x0005 | 0x0006 => 4,
+            0x0007 | 0x0008 => 8,
+            0x0009 | 0x000A => 16,
+            0x4001..=0x400A => 4,
+            0xFFFF => 1,
+            _ => 0,
+        }
+    }
 }
 
 impl From<i8> for DataType {
@@ -217,6 +220,78 @@ impl From<u64> for DataType {
     }
 }
 
+impl From<i128> for DataType {
+    fn from(value: i128) -> Self {
+        DataType::INT128(value)
+    }
+}
+
+impl From<u128> for DataType {
+    fn from(value: u128) -> Self {
+        DataType::UINT128(value)
+    }
+}
+
+impl From<Vec<i8>> for DataType {
+    fn from(value: Vec<i8>) -> Self {
+        DataType::AINT8(value)
+    }
+}
+
+impl From<Vec<u8>> for DataType {
+    fn from(value: Vec<u8>) -> Self {
+        DataType::AUINT8(value)
+    }
+}
+
+impl From<Vec<i16>> for DataType {
+    fn from(value: Vec<i16>) -> Self {
+        DataType::AINT16(value)
+    }
+}
+
+impl From<Vec<u16>> for DataType {
+    fn from(value: Vec<u16>) -> Self {
+        DataType::AUINT16(value)
+    }
+}
+
+impl From<Vec<i32>> for DataType {
+    fn from(value: Vec<i32>) -> Self {
+        DataType::AINT32(value)
+    }
+}
+
+impl From<Vec<u32>> for DataType {
+    fn from(value: Vec<u32>) -> Self {
+        DataType::AUINT32(value)
+    }
+}
+
+impl From<Vec<i64>> for DataType {
+    fn from(value: Vec<i64>) -> Self {
+        DataType::AINT64(value)
+    }
+}
+
+impl From<Vec<u64>> for DataType {
+    fn from(value: Vec<u64>) -> Self {
+        DataType::AUINT64(value)
+    }
+}
+
+impl From<Vec<i128>> for DataType {
+    fn from(value: Vec<i128>) -> Self {
+        DataType::AINT128(value)
+    }
+}
+
+impl From<Vec<u128>> for DataType {
+    fn from(value: Vec<u128>) -> Self {
+        DataType::AUINT128(value)
+    }
+}
+
 impl From<&str> for DataType {
     fn from(value: &str) -> Self {
         DataType::STR(value.to_owned())
@@ -229,7 +304,251 @@ impl From<String> for DataType {
     }
 }
 
-#[derive(Debug)]
+fn mismatch(expected: &str, value: &DataType) -> Error {
+    Error::Malformed(format!(
+        "Expected a value of type {}, got {:?}",
+        expected, value
+    ))
+}
+
+impl TryFrom<DataType> for i8 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::INT8(v) => Ok(v),
+            v => Err(mismatch("INT8", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for u8 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::UINT8(v) => Ok(v),
+            v => Err(mismatch("UINT8", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for i16 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::INT16(v) => Ok(v),
+            v => Err(mismatch("INT16", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for u16 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::UINT16(v) => Ok(v),
+            v => Err(mismatch("UINT16", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for i32 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::INT32(v) => Ok(v),
+            v => Err(mismatch("INT32", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for u32 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::UINT32(v) => Ok(v),
+            v => Err(mismatch("UINT32", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for i64 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::INT64(v) => Ok(v),
+            v => Err(mismatch("INT64", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for u64 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::UINT64(v) => Ok(v),
+            v => Err(mismatch("UINT64", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for i128 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::INT128(v) => Ok(v),
+            v => Err(mismatch("INT128", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for u128 {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::UINT128(v) => Ok(v),
+            v => Err(mismatch("UINT128", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<i8> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AINT8(v) => Ok(v),
+            v => Err(mismatch("AINT8", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<u8> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AUINT8(v) => Ok(v),
+            v => Err(mismatch("AUINT8", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<i16> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AINT16(v) => Ok(v),
+            v => Err(mismatch("AINT16", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<u16> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AUINT16(v) => Ok(v),
+            v => Err(mismatch("AUINT16", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<i32> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AINT32(v) => Ok(v),
+            v => Err(mismatch("AINT32", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<u32> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AUINT32(v) => Ok(v),
+            v => Err(mismatch("AUINT32", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<i64> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AINT64(v) => Ok(v),
+            v => Err(mismatch("AINT64", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<u64> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AUINT64(v) => Ok(v),
+            v => Err(mismatch("AUINT64", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<i128> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AINT128(v) => Ok(v),
+            v => Err(mismatch("AINT128", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for Vec<u128> {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::AUINT128(v) => Ok(v),
+            v => Err(mismatch("AUINT128", &v)),
+        }
+    }
+}
+
+impl TryFrom<DataType> for String {
+    type Error = Error;
+
+    fn try_from(value: DataType) -> Result<Self, Error> {
+        match value {
+            DataType::STR(v) => Ok(v),
+            v => Err(mismatch("STR", &v)),
+        }
+    }
+}
+
+/// The allowed values of a device property.
+///
+/// With the `serde` feature, the form is tagged by `"form"`, e.g.
+/// `{"form": "Range", "min_value": ..., "max_value": ..., "step": ...}` or `{"form": "None"}`.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "form"))]
 pub enum FormData {
     None,
     Range {
@@ -241,3 +560,106 @@ pub enum FormData {
         array: Vec<DataType>,
     },
 }
+
+impl FormData {
+    /// Read the form flag and form of a property of type `data_type`.
+    pub fn read_form<T: Read>(data_type: u16, reader: &mut T) -> Result<FormData, Error> {
+        Ok(match reader.read_ptp_u8()? {
+            // 0x00 => FormData::None,
+            0x01 => FormData::Range {
+                min_value: DataType::read_type(data_type, reader)?,
+                max_value: DataType::read_type(data_type, reader)?,
+                step: DataType::read_type(data_type, reader)?,
+            },
+            0x02 => FormData::Enumeration {
+                array: {
+                    let len = reader.read_ptp_u16()? as usize;
+                    let min_size = max(DataType::min_encoded_len(data_type), 1);
+                    let mut arr = Vec::with_capacity(min(len, MAX_PREALLOC_LEN / min_size));
+                    for _ in 0..len {
+                        arr.push(DataType::read_type(data_type, reader)?);
+                    }
+                    arr
+                },
+            },
+            _ => FormData::None,
+        })
+    }
+
+    /// Write the form flag and form.
+    pub fn write_form<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        match self {
+            FormData::None => w.write_ptp_u8(0x00),
+            FormData::Range {
+                min_value,
+                max_value,
+                step,
+            } => {
+                w.write_ptp_u8(0x01)?;
+                for value in &[min_value, max_value, step] {
+                    value.encode_into(w)?;
+                }
+                Ok(())
+            }
+            FormData::Enumeration { array } => {
+                if array.len() > u16::MAX as usize {
+                    return Err(Error::Malformed(format!(
+                        "Enumeration of {} values exceeds the PTP limit",
+                        array.len()
+                    )));
+                }
+                w.write_ptp_u8(0x02)?;
+                w.write_ptp_u16(array.len() as u16)?;
+                for value in array {
+                    value.encode_into(w)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+// (de)serializes integers as decimal strings
+#[cfg(feature = "serde")]
+mod decimal {
+    use serde::{de, Deserialize, Deserializer, Serializer};
+    use std::{fmt::Display, str::FromStr};
+
+    pub fn serialize<T: Display, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(d)?.parse().map_err(de::Error::custom)
+    }
+
+    pub mod vec {
+        use serde::{de, ser::SerializeSeq, Deserialize, Deserializer, Serializer};
+        use std::{fmt::Display, str::FromStr};
+
+        pub fn serialize<T: Display, S: Serializer>(values: &[T], s: S) -> Result<S::Ok, S::Error> {
+            let mut seq = s.serialize_seq(Some(values.len()))?;
+            for value in values {
+                seq.serialize_element(&value.to_string())?;
+            }
+            seq.end()
+        }
+
+        pub fn deserialize<'de, T, D>(d: D) -> Result<Vec<T>, D::Error>
+        where
+            T: FromStr,
+            T::Err: Display,
+            D: Deserializer<'de>,
+        {
+            Vec::<String>::deserialize(d)?
+                .iter()
+                .map(|value| value.parse().map_err(de::Error::custom))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use super::Error;
+use std::{
+    convert::TryFrom,
+    fmt,
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A date and time in the ISO 8601 subset used by PTP, `YYYYMMDDThhmmss[.s][Z|±hhmm]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PtpDateTime {
+    pub year: u16,
+    /// 1 to 12
+    pub month: u8,
+    /// 1 to 31
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    /// Tenths of a second, if present
+    pub tenths: Option<u8>,
+    /// Offset from UTC in minutes. `None` for a local time without zone designator.
+    pub utc_offset: Option<i16>,
+}
+
+impl PtpDateTime {
+    /// Parse a PTP date-time string, as found in `ObjectInfo` and the `DateTime` property.
+    pub fn parse(s: &str) -> Result<PtpDateTime, Error> {
+        let invalid = || Error::Malformed(format!("Invalid PTP date-time {:?}", s));
+        // some devices pad the string with NULs or spaces
+        let s = s.trim_end_matches(&['\0', ' '][..]);
+        if !s.is_ascii() || s.len() < 15 || s.as_bytes()[8] != b'T' {
+            return Err(invalid());
+        }
+
+        let num = |range: std::ops::Range<usize>| -> Result<u16, Error> {
+            let digits = &s[range];
+            if digits.bytes().all(|b| b.is_ascii_digit()) {
+                digits.parse().map_err(|_| invalid())
+            } else {
+                Err(invalid())
+            }
+        };
+
+        let mut dt = PtpDateTime {
+            year: num(0..4)?,
+            month: num(4..6)? as u8,
+            day: num(6..8)? as u8,
+            hour: num(9..11)? as u8,
+            minute: num(11..13)? as u8,
+            second: num(13..15)? as u8,
+            tenths: None,
+            utc_offset: None,
+        };
+
+        let mut rest = &s[15..];
+        if rest.starts_with('.') {
+            let digits = rest[1..].bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                return Err(invalid());
+            }
+            // finer precision than tenths is allowed but dropped
+            dt.tenths = Some(rest.as_bytes()[1] - b'0');
+            rest = &rest[1 + digits..];
+        }
+
+        match rest.as_bytes() {
+            [] => {}
+            [b'Z'] => dt.utc_offset = Some(0),
+            [sign @ b'+', ..] | [sign @ b'-', ..] if rest.len() == 5 => {
+                let base = s.len() - 4;
+                let hours = num(base..base + 2)?;
+                let minutes = num(base + 2..base + 4)?;
+                if hours >= 24 || minutes >= 60 {
+                    return Err(invalid());
+                }
+                let offset = (hours * 60 + minutes) as i16;
+                dt.utc_offset = Some(if *sign == b'-' { -offset } else { offset });
+            }
+            _ => return Err(invalid()),
+        }
+
+        if dt.is_valid() {
+            Ok(dt)
+        } else {
+            Err(invalid())
+        }
+    }
+
+    fn is_valid(&self) -> bool {
+        self.month >= 1
+            && self.month <= 12
+            && self.day >= 1
+            && self.day <= days_in_month(i64::from(self.year), self.month)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+    }
+
+    /// The current time of the host clock, in UTC.
+    pub fn now() -> Result<PtpDateTime, Error> {
+        PtpDateTime::from_system_time(SystemTime::now())
+    }
+
+    /// Convert a `SystemTime` to UTC, truncated to whole seconds.
+    /// Fails for times outside the years 0 to 9999.
+    pub fn from_system_time(time: SystemTime) -> Result<PtpDateTime, Error> {
+        PtpDateTime::from_system_time_with_offset(time, 0)
+    }
+
+    /// Convert a `SystemTime` to the local time at `utc_offset` minutes from UTC, truncated to
+    /// whole seconds. Fails for times outside the years 0 to 9999, and for offsets beyond
+    /// ±23:59.
+    pub fn from_system_time_with_offset(
+        time: SystemTime,
+        utc_offset: i16,
+    ) -> Result<PtpDateTime, Error> {
+        let utc_offset = ptp_utc_offset(utc_offset)?;
+        let secs = match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_secs() as i64,
+            Err(e) => {
+                let d = e.duration();
+                -(d.as_secs() as i64) - if d.subsec_nanos() > 0 { 1 } else { 0 }
+            }
+        } + i64::from(utc_offset) * 60;
+
+        let days = secs.div_euclid(86400);
+        let secs_of_day = secs.rem_euclid(86400);
+        let (year, month, day) = civil_from_days(days);
+        Ok(PtpDateTime {
+            year: ptp_year(year)?,
+            month,
+            day,
+            hour: (secs_of_day / 3600) as u8,
+            minute: (secs_of_day / 60 % 60) as u8,
+            second: (secs_of_day % 60) as u8,
+            tenths: None,
+            utc_offset: Some(utc_offset),
+        })
+    }
+
+    /// Convert to a `SystemTime`. A time without zone designator is taken to be UTC.
+    pub fn to_system_time(&self) -> SystemTime {
+        let days = days_from_civil(i64::from(self.year), self.month, self.day);
+        let secs = days * 86400
+            + i64::from(self.hour) * 3600
+            + i64::from(self.minute) * 60
+            + i64::from(self.second)
+            - i64::from(self.utc_offset.unwrap_or(0)) * 60;
+        let subsec = Duration::from_millis(u64::from(self.tenths.unwrap_or(0)) * 100);
+
+        if secs >= 0 {
+            UNIX_EPOCH + Duration::from_secs(secs as u64) + subsec
+        } else {
+            UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs()) + subsec
+        }
+    }
+}
+
+impl fmt::Display for PtpDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}{:02}{:02}T{:02}{:02}{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if let Some(tenths) = self.tenths {
+            write!(f, ".{}", tenths)?;
+        }
+        match self.utc_offset {
+            None => Ok(()),
+            Some(0) => write!(f, "Z"),
+            Some(offset) => write!(
+                f,
+                "{}{:02}{:02}",
+                if offset < 0 { '-' } else { '+' },
+                offset.unsigned_abs() / 60,
+                offset.unsigned_abs() % 60
+            ),
+        }
+    }
+}
+
+impl FromStr for PtpDateTime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<PtpDateTime, Error> {
+        PtpDateTime::parse(s)
+    }
+}
+
+impl TryFrom<SystemTime> for PtpDateTime {
+    type Error = Error;
+
+    fn try_from(time: SystemTime) -> Result<PtpDateTime, Error> {
+        PtpDateTime::from_system_time(time)
+    }
+}
+
+impl From<PtpDateTime> for SystemTime {
+    fn from(dt: PtpDateTime) -> SystemTime {
+        dt.to_system_time()
+    }
+}
+
+// the string form has room for four-digit years only
+// ±hhmm can express no more than 23:59
+fn ptp_utc_offset(minutes: i16) -> Result<i16, Error> {
+    if (-(23 * 60 + 59)..=23 * 60 + 59).contains(&minutes) {
+        Ok(minutes)
+    } else {
+        Err(Error::Malformed(format!(
+            "UTC offset of {} minutes is outside the range of PTP date-times",
+            minutes
+        )))
+    }
+}
+
+fn ptp_year(year: i64) -> Result<u16, Error> {
+    if (0..=9999).contains(&year) {
+        Ok(year as u16)
+    } else {
+        Err(Error::Malformed(format!(
+            "Year {} is outside the range of PTP date-times",
+            year
+        )))
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+fn days_in_month(year: i64, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// days since 1970-01-01 in the proleptic Gregorian calendar, after Howard Hinnant's algorithm
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = i64::from(month);
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u8;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u8;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::{ptp_year, PtpDateTime};
+    use crate::Error;
+    use chrono::{
+        DateTime, Datelike, FixedOffset, NaiveDate, NaiveDateTime, Offset, TimeZone, Timelike,
+    };
+    use std::convert::TryFrom;
+
+    impl TryFrom<NaiveDateTime> for PtpDateTime {
+        type Error = Error;
+
+        /// Fails for years outside 0 to 9999.
+        fn try_from(dt: NaiveDateTime) -> Result<PtpDateTime, Error> {
+            Ok(PtpDateTime {
+                year: ptp_year(i64::from(dt.year()))?,
+                month: dt.month() as u8,
+                day: dt.day() as u8,
+                hour: dt.hour() as u8,
+                minute: dt.minute() as u8,
+                second: dt.second() as u8,
+                tenths: None,
+                utc_offset: None,
+            })
+        }
+    }
+
+    impl<Tz: TimeZone> TryFrom<DateTime<Tz>> for PtpDateTime {
+        type Error = Error;
+
+        /// Fails for years outside 0 to 9999.
+        fn try_from(dt: DateTime<Tz>) -> Result<PtpDateTime, Error> {
+            let offset = dt.offset().fix().local_minus_utc() / 60;
+            Ok(PtpDateTime {
+                utc_offset: Some(offset as i16),
+                ..PtpDateTime::try_from(dt.naive_local())?
+            })
+        }
+    }
+
+    impl TryFrom<PtpDateTime> for NaiveDateTime {
+        type Error = Error;
+
+        /// Drops the zone designator, keeping the local time.
+        fn try_from(dt: PtpDateTime) -> Result<NaiveDateTime, Error> {
+            NaiveDate::from_ymd_opt(i32::from(dt.year), u32::from(dt.month), u32::from(dt.day))
+                .and_then(|date| {
+                    date.and_hms_milli_opt(
+                        u32::from(dt.hour),
+                        u32::from(dt.minute),
+                        u32::from(dt.second),
+                        u32::from(dt.tenths.unwrap_or(0)) * 100,
+                    )
+                })
+                .ok_or_else(|| Error::Malformed(format!("Invalid PTP date-time {}", dt)))
+        }
+    }
+
+    impl TryFrom<PtpDateTime> for DateTime<FixedOffset> {
+        type Error = Error;
+
+        /// A time without zone designator is taken to be UTC.
+        fn try_from(dt: PtpDateTime) -> Result<DateTime<FixedOffset>, Error> {
+            let naive = NaiveDateTime::try_from(dt)?;
+            FixedOffset::east_opt(i32::from(dt.utc_offset.unwrap_or(0)) * 60)
+                .and_then(|offset| offset.from_local_datetime(&naive).single())
+                .ok_or_else(|| Error::Malformed(format!("Invalid PTP date-time {}", dt)))
+        }
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_impls {
+    use super::{ptp_utc_offset, ptp_year, PtpDateTime};
+    use crate::Error;
+    use std::convert::TryFrom;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    impl TryFrom<PrimitiveDateTime> for PtpDateTime {
+        type Error = Error;
+
+        /// Fails for years outside 0 to 9999.
+        fn try_from(dt: PrimitiveDateTime) -> Result<PtpDateTime, Error> {
+            Ok(PtpDateTime {
+                year: ptp_year(i64::from(dt.year()))?,
+                month: u8::from(dt.month()),
+                day: dt.day(),
+                hour: dt.hour(),
+                minute: dt.minute(),
+                second: dt.second(),
+                tenths: None,
+                utc_offset: None,
+            })
+        }
+    }
+
+    impl TryFrom<OffsetDateTime> for PtpDateTime {
+        type Error = Error;
+
+        /// Fails for years outside 0 to 9999, and for offsets beyond ±23:59.
+        fn try_from(dt: OffsetDateTime) -> Result<PtpDateTime, Error> {
+            Ok(PtpDateTime {
+                utc_offset: Some(ptp_utc_offset(dt.offset().whole_minutes())?),
+                ..PtpDateTime::try_from(PrimitiveDateTime::new(dt.date(), dt.time()))?
+            })
+        }
+    }
+
+    impl TryFrom<PtpDateTime> for PrimitiveDateTime {
+        type Error = Error;
+
+        /// Drops the zone designator, keeping the local time.
+        fn try_from(dt: PtpDateTime) -> Result<PrimitiveDateTime, Error> {
+            let invalid = |_| Error::Malformed(format!("Invalid PTP date-time {}", dt));
+            let month = Month::try_from(dt.month).map_err(invalid)?;
+            let date =
+                Date::from_calendar_date(i32::from(dt.year), month, dt.day).map_err(invalid)?;
+            let time = Time::from_hms_milli(
+                dt.hour,
+                dt.minute,
+                dt.second,
+                u16::from(dt.tenths.unwrap_or(0)) * 100,
+            )
+            .map_err(invalid)?;
+            Ok(PrimitiveDateTime::new(date, time))
+        }
+    }
+
+    impl TryFrom<PtpDateTime> for OffsetDateTime {
+        type Error = Error;
+
+        /// A time without zone designator is taken to be UTC.
+        fn try_from(dt: PtpDateTime) -> Result<OffsetDateTime, Error> {
+            let offset = dt.utc_offset.unwrap_or(0);
+            let offset = UtcOffset::from_hms((offset / 60) as i8, (offset % 60) as i8, 0)
+                .map_err(|_| Error::Malformed(format!("Invalid PTP date-time {}", dt)))?;
+            Ok(PrimitiveDateTime::try_from(dt)?.assume_offset(offset))
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use super::StandardResponseCode;
+use super::{
+    vendor::{self, CodeKind},
+    StandardResponseCode,
+};
 use std::{fmt, io};
 
 /// An error in a PTP command
@@ -7,9 +10,18 @@ pub enum Error {
     /// PTP Responder returned a status code other than Ok, either a constant in StandardResponseCode or a vendor-defined code
     Response(u16),
 
+    /// The device reported a full store, by storage ID, while capturing to it
+    StoreFull(u32),
+
     /// Data received was malformed
     Malformed(String),
 
+    /// The operation was refused before being sent, e.g. because a safeguard did not pass
+    Refused(String),
+
+    /// The device does not provide what was asked for, although it did not report an error
+    Unsupported(String),
+
     /// Another libusb error
     Usb(libusb::Error),
 
@@ -20,15 +32,26 @@ pub enum Error {
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            Error::Response(r) => write!(
-                f,
-                "{} (0x{:04x})",
-                StandardResponseCode::name(r).unwrap_or("Unknown"),
-                r
-            ),
+            Error::Response(r) => {
+                // the vendor is not known here, so name the code as every vendor that uses it
+                let names = match vendor::code_name(None, CodeKind::Response, r) {
+                    Some(name) => vec![name.to_owned()],
+                    None => vendor::vendor_code_names(CodeKind::Response, r)
+                        .into_iter()
+                        .map(|(vendor, name)| format!("{:?} {}", vendor, name))
+                        .collect(),
+                };
+                match names.len() {
+                    0 => write!(f, "Unknown (0x{:04x})", r),
+                    _ => write!(f, "{} (0x{:04x})", names.join(" or "), r),
+                }
+            }
+            Error::StoreFull(id) => write!(f, "Store 0x{:08x} is full", id),
             Error::Usb(ref e) => write!(f, "USB error: {}", e),
             Error::Io(ref e) => write!(f, "IO error: {}", e),
             Error::Malformed(ref e) => write!(f, "{}", e),
+            Error::Refused(ref e) => write!(f, "Refused: {}", e),
+            Error::Unsupported(ref e) => write!(f, "Unsupported: {}", e),
         }
     }
 }
@@ -37,7 +60,10 @@ impl ::std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::Response(r) => StandardResponseCode::name(r).unwrap_or("<vendor-defined code>"),
+            Error::StoreFull(_) => "Store is full",
             Error::Malformed(ref m) => m,
+            Error::Refused(ref m) => m,
+            Error::Unsupported(ref m) => m,
             Error::Usb(ref e) => e.description(),
             Error::Io(ref e) => e.description(),
         }
@@ -0,0 +1,199 @@
+use super::camera::{ContainerInfo, ContainerType, CONTAINER_INFO_SIZE};
+use super::vendor::mtp::MtpEventCode;
+use super::{Error, EventCode, PropCode, Read, StandardEventCode, StorageInfo};
+use std::io::Cursor;
+
+/// An event sent by the responder on the interrupt endpoint
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PtpEvent {
+    /// The responder cancelled the given transaction
+    CancelTransaction {
+        transaction_id: u32,
+    },
+    ObjectAdded {
+        handle: u32,
+    },
+    ObjectRemoved {
+        handle: u32,
+    },
+    StoreAdded {
+        storage_id: u32,
+    },
+    StoreRemoved {
+        storage_id: u32,
+    },
+    DevicePropChanged {
+        prop: PropCode,
+    },
+    ObjectInfoChanged {
+        handle: u32,
+    },
+    /// The capabilities in `DeviceInfo` changed and should be fetched again
+    DeviceInfoChanged,
+    /// The responder asks the initiator to download the given object
+    RequestObjectTransfer {
+        handle: u32,
+    },
+    StoreFull {
+        storage_id: u32,
+    },
+    /// The responder was reset, closing all sessions
+    DeviceReset,
+    StorageInfoChanged {
+        storage_id: u32,
+    },
+    /// The capture started by the given `InitiateCapture` transaction finished
+    CaptureComplete {
+        transaction_id: u32,
+    },
+    /// Events were dropped, so state held by the initiator should be refreshed
+    UnreportedStatus,
+    /// MTP: an object property changed
+    ObjectPropChanged {
+        handle: u32,
+        prop: PropCode,
+    },
+    /// MTP: the description of an object property changed
+    ObjectPropDescChanged {
+        prop: PropCode,
+        format: u16,
+    },
+    /// Any other event, including vendor-defined ones
+    Other {
+        code: EventCode,
+        transaction_id: u32,
+        params: Vec<u32>,
+    },
+}
+
+impl PtpEvent {
+    /// Decode an event container, as received on the interrupt endpoint.
+    pub fn decode(buf: &[u8]) -> Result<PtpEvent, Error> {
+        let cinfo = ContainerInfo::parse(buf)?;
+        if cinfo.kind != ContainerType::Event {
+            return Err(Error::Malformed(format!(
+                "Expected an event container, got {:?}",
+                cinfo.kind
+            )));
+        }
+        if buf.len() < CONTAINER_INFO_SIZE + cinfo.payload_len {
+            return Err(Error::Malformed(format!(
+                "Event container of {} bytes, received {}",
+                CONTAINER_INFO_SIZE + cinfo.payload_len,
+                buf.len()
+            )));
+        }
+
+        let mut cur =
+            Cursor::new(&buf[CONTAINER_INFO_SIZE..CONTAINER_INFO_SIZE + cinfo.payload_len]);
+        let params = (0..cinfo.payload_len / 4)
+            .map(|_| cur.read_ptp_u32())
+            .collect::<Result<Vec<_>, _>>()?;
+        PtpEvent::from_parts(cinfo.code, cinfo.tid, params)
+    }
+
+    /// Build an event from its code, transaction ID and parameters.
+    pub fn from_parts(
+        code: EventCode,
+        transaction_id: u32,
+        params: Vec<u32>,
+    ) -> Result<PtpEvent, Error> {
+        use self::PtpEvent::*;
+
+        let param = |i: usize| {
+            params.get(i).cloned().ok_or_else(|| {
+                Error::Malformed(format!(
+                    "Event 0x{:04x} with {} parameters, expected at least {}",
+                    code,
+                    params.len(),
+                    i + 1
+                ))
+            })
+        };
+
+        Ok(match code {
+            StandardEventCode::CancelTransaction => CancelTransaction { transaction_id },
+            StandardEventCode::ObjectAdded => ObjectAdded { handle: param(0)? },
+            StandardEventCode::ObjectRemoved => ObjectRemoved { handle: param(0)? },
+            StandardEventCode::StoreAdded => StoreAdded {
+                storage_id: param(0)?,
+            },
+            StandardEventCode::StoreRemoved => StoreRemoved {
+                storage_id: param(0)?,
+            },
+            StandardEventCode::DevicePropChanged => DevicePropChanged {
+                prop: param(0)? as PropCode,
+            },
+            StandardEventCode::ObjectInfoChanged => ObjectInfoChanged { handle: param(0)? },
+            StandardEventCode::DeviceInfoChanged => DeviceInfoChanged,
+            StandardEventCode::RequestObjectTransfer => RequestObjectTransfer { handle: param(0)? },
+            StandardEventCode::StoreFull => StoreFull {
+                storage_id: param(0)?,
+            },
+            StandardEventCode::DeviceReset => DeviceReset,
+            StandardEventCode::StorageInfoChanged => StorageInfoChanged {
+                storage_id: param(0)?,
+            },
+            StandardEventCode::CaptureComplete => CaptureComplete { transaction_id },
+            StandardEventCode::UnreportedStatus => UnreportedStatus,
+            MtpEventCode::ObjectPropChanged => ObjectPropChanged {
+                handle: param(0)?,
+                prop: param(1)? as PropCode,
+            },
+            MtpEventCode::ObjectPropDescChanged => ObjectPropDescChanged {
+                prop: param(0)? as PropCode,
+                format: param(1)? as u16,
+            },
+            _ => Other {
+                code,
+                transaction_id,
+                params,
+            },
+        })
+    }
+
+    /// The event code this event was decoded from
+    pub fn code(&self) -> EventCode {
+        use self::PtpEvent::*;
+        match *self {
+            CancelTransaction { .. } => StandardEventCode::CancelTransaction,
+            ObjectAdded { .. } => StandardEventCode::ObjectAdded,
+            ObjectRemoved { .. } => StandardEventCode::ObjectRemoved,
+            StoreAdded { .. } => StandardEventCode::StoreAdded,
+            StoreRemoved { .. } => StandardEventCode::StoreRemoved,
+            DevicePropChanged { .. } => StandardEventCode::DevicePropChanged,
+            ObjectInfoChanged { .. } => StandardEventCode::ObjectInfoChanged,
+            DeviceInfoChanged => StandardEventCode::DeviceInfoChanged,
+            RequestObjectTransfer { .. } => StandardEventCode::RequestObjectTransfer,
+            StoreFull { .. } => StandardEventCode::StoreFull,
+            DeviceReset => StandardEventCode::DeviceReset,
+            StorageInfoChanged { .. } => StandardEventCode::StorageInfoChanged,
+            CaptureComplete { .. } => StandardEventCode::CaptureComplete,
+            UnreportedStatus => StandardEventCode::UnreportedStatus,
+            ObjectPropChanged { .. } => MtpEventCode::ObjectPropChanged,
+            ObjectPropDescChanged { .. } => MtpEventCode::ObjectPropDescChanged,
+            Other { code, .. } => code,
+        }
+    }
+}
+
+/// A change to the set of stores, as reported by `Camera::wait_storage_change`
+#[derive(Debug)]
+pub enum StorageChange {
+    /// A store was added, e.g. a card was inserted
+    Added { storage_id: u32, info: StorageInfo },
+    /// A store was removed and its objects are no longer valid
+    Removed { storage_id: u32 },
+    /// The `StorageInfo` of a store changed
+    InfoChanged { storage_id: u32, info: StorageInfo },
+}
+
+impl StorageChange {
+    pub fn storage_id(&self) -> u32 {
+        match *self {
+            StorageChange::Added { storage_id, .. }
+            | StorageChange::Removed { storage_id }
+            | StorageChange::InfoChanged { storage_id, .. } => storage_id,
+        }
+    }
+}
@@ -1,21 +1,39 @@
 #![allow(non_snake_case)]
 #[macro_use]
 extern crate log;
-
-use byteorder::LittleEndian;
-use std::io::Cursor;
+// lets the code generated by ptp-derive refer to `::ptp` inside this crate too
+extern crate self as ptp;
 
 mod camera;
 mod data_type;
+mod datetime;
 mod error;
+mod event;
+mod ptpip;
 mod read;
+pub mod vendor;
+mod view;
+mod write;
 
-pub use self::camera::Camera;
+pub use self::camera::{Camera, OpenCapture};
+// container headers are only exposed to the fuzz targets
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub use self::camera::{ContainerInfo, ContainerType};
 pub use self::data_type::{DataType, FormData};
+pub use self::datetime::PtpDateTime;
 pub use self::error::Error;
-pub use self::read::Read;
+pub use self::event::{PtpEvent, StorageChange};
+pub use self::ptpip::{PtpIpHandshake, PtpIpOptions, PtpIpResponder, FUJI_PORT, PTPIP_PORT};
+pub use self::read::{CountingReader, LimitedReader, PtpDecode, Read, ReadEnd};
+pub use self::vendor::{VendorExtension, VendorExtensions, VendorId};
+pub use self::view::{ObjectInfoRef, PtpStr};
+pub use self::write::{PtpEncode, Write};
+pub use ptp_derive::PtpDataset;
 
 pub type ResponseCode = u16;
+pub type EventCode = u16;
+pub type PropCode = u16;
 
 #[allow(non_upper_case_globals)]
 pub mod StandardResponseCode {
@@ -167,8 +185,127 @@ pub mod StandardCommandCode {
     }
 }
 
+#[allow(non_upper_case_globals)]
+pub mod StandardEventCode {
+    use super::EventCode;
+
+    pub const Undefined: EventCode = 0x4000;
+    pub const CancelTransaction: EventCode = 0x4001;
+    pub const ObjectAdded: EventCode = 0x4002;
+    pub const ObjectRemoved: EventCode = 0x4003;
+    pub const StoreAdded: EventCode = 0x4004;
+    pub const StoreRemoved: EventCode = 0x4005;
+    pub const DevicePropChanged: EventCode = 0x4006;
+    pub const ObjectInfoChanged: EventCode = 0x4007;
+    pub const DeviceInfoChanged: EventCode = 0x4008;
+    pub const RequestObjectTransfer: EventCode = 0x4009;
+    pub const StoreFull: EventCode = 0x400A;
+    pub const DeviceReset: EventCode = 0x400B;
+    pub const StorageInfoChanged: EventCode = 0x400C;
+    pub const CaptureComplete: EventCode = 0x400D;
+    pub const UnreportedStatus: EventCode = 0x400E;
+
+    pub fn name(v: EventCode) -> Option<&'static str> {
+        match v {
+            Undefined => Some("Undefined"),
+            CancelTransaction => Some("CancelTransaction"),
+            ObjectAdded => Some("ObjectAdded"),
+            ObjectRemoved => Some("ObjectRemoved"),
+            StoreAdded => Some("StoreAdded"),
+            StoreRemoved => Some("StoreRemoved"),
+            DevicePropChanged => Some("DevicePropChanged"),
+            ObjectInfoChanged => Some("ObjectInfoChanged"),
+            DeviceInfoChanged => Some("DeviceInfoChanged"),
+            RequestObjectTransfer => Some("RequestObjectTransfer"),
+            StoreFull => Some("StoreFull"),
+            DeviceReset => Some("DeviceReset"),
+            StorageInfoChanged => Some("StorageInfoChanged"),
+            CaptureComplete => Some("CaptureComplete"),
+            UnreportedStatus => Some("UnreportedStatus"),
+            _ => None,
+        }
+    }
+}
+
+#[allow(non_upper_case_globals)]
+pub mod StandardPropCode {
+    use super::PropCode;
+
+    pub const Undefined: PropCode = 0x5000;
+    pub const BatteryLevel: PropCode = 0x5001;
+    pub const FunctionalMode: PropCode = 0x5002;
+    pub const ImageSize: PropCode = 0x5003;
+    pub const CompressionSetting: PropCode = 0x5004;
+    pub const WhiteBalance: PropCode = 0x5005;
+    pub const RGBGain: PropCode = 0x5006;
+    pub const FNumber: PropCode = 0x5007;
+    pub const FocalLength: PropCode = 0x5008;
+    pub const FocusDistance: PropCode = 0x5009;
+    pub const FocusMode: PropCode = 0x500A;
+    pub const ExposureMeteringMode: PropCode = 0x500B;
+    pub const FlashMode: PropCode = 0x500C;
+    pub const ExposureTime: PropCode = 0x500D;
+    pub const ExposureProgramMode: PropCode = 0x500E;
+    pub const ExposureIndex: PropCode = 0x500F;
+    pub const ExposureBiasCompensation: PropCode = 0x5010;
+    pub const DateTime: PropCode = 0x5011;
+    pub const CaptureDelay: PropCode = 0x5012;
+    pub const StillCaptureMode: PropCode = 0x5013;
+    pub const Contrast: PropCode = 0x5014;
+    pub const Sharpness: PropCode = 0x5015;
+    pub const DigitalZoom: PropCode = 0x5016;
+    pub const EffectMode: PropCode = 0x5017;
+    pub const BurstNumber: PropCode = 0x5018;
+    pub const BurstInterval: PropCode = 0x5019;
+    pub const TimelapseNumber: PropCode = 0x501A;
+    pub const TimelapseInterval: PropCode = 0x501B;
+    pub const FocusMeteringMode: PropCode = 0x501C;
+    pub const UploadURL: PropCode = 0x501D;
+    pub const Artist: PropCode = 0x501E;
+    pub const CopyrightInfo: PropCode = 0x501F;
+
+    pub fn name(v: PropCode) -> Option<&'static str> {
+        match v {
+            Undefined => Some("Undefined"),
+            BatteryLevel => Some("BatteryLevel"),
+            FunctionalMode => Some("FunctionalMode"),
+            ImageSize => Some("ImageSize"),
+            CompressionSetting => Some("CompressionSetting"),
+            WhiteBalance => Some("WhiteBalance"),
+            RGBGain => Some("RGBGain"),
+            FNumber => Some("FNumber"),
+            FocalLength => Some("FocalLength"),
+            FocusDistance => Some("FocusDistance"),
+            FocusMode => Some("FocusMode"),
+            ExposureMeteringMode => Some("ExposureMeteringMode"),
+            FlashMode => Some("FlashMode"),
+            ExposureTime => Some("ExposureTime"),
+            ExposureProgramMode => Some("ExposureProgramMode"),
+            ExposureIndex => Some("ExposureIndex"),
+            ExposureBiasCompensation => Some("ExposureBiasCompensation"),
+            DateTime => Some("DateTime"),
+            CaptureDelay => Some("CaptureDelay"),
+            StillCaptureMode => Some("StillCaptureMode"),
+            Contrast => Some("Contrast"),
+            Sharpness => Some("Sharpness"),
+            DigitalZoom => Some("DigitalZoom"),
+            EffectMode => Some("EffectMode"),
+            BurstNumber => Some("BurstNumber"),
+            BurstInterval => Some("BurstInterval"),
+            TimelapseNumber => Some("TimelapseNumber"),
+            TimelapseInterval => Some("TimelapseInterval"),
+            FocusMeteringMode => Some("FocusMeteringMode"),
+            UploadURL => Some("UploadURL"),
+            Artist => Some("Artist"),
+            CopyrightInfo => Some("CopyrightInfo"),
+            _ => None,
+        }
+    }
+}
+
 #[allow(non_snake_case)]
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, PtpDataset)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DeviceInfo {
     pub Version: u16,
     pub VendorExID: u32,
@@ -188,29 +325,58 @@ pub struct DeviceInfo {
 
 impl DeviceInfo {
     pub fn decode(buf: &[u8]) -> Result<DeviceInfo, Error> {
-        let mut cur = Cursor::new(buf);
-
-        Ok(DeviceInfo {
-            Version: cur.read_ptp_u16()?,
-            VendorExID: cur.read_ptp_u32()?,
-            VendorExVersion: cur.read_ptp_u16()?,
-            VendorExtensionDesc: cur.read_ptp_str()?,
-            FunctionalMode: cur.read_ptp_u16()?,
-            OperationsSupported: cur.read_ptp_u16_vec()?,
-            EventsSupported: cur.read_ptp_u16_vec()?,
-            DevicePropertiesSupported: cur.read_ptp_u16_vec()?,
-            CaptureFormats: cur.read_ptp_u16_vec()?,
-            ImageFormats: cur.read_ptp_u16_vec()?,
-            Manufacturer: cur.read_ptp_str()?,
-            Model: cur.read_ptp_str()?,
-            DeviceVersion: cur.read_ptp_str()?,
-            SerialNumber: cur.read_ptp_str()?,
-        })
+        DeviceInfo::from_bytes(buf)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    /// Parse `VendorExID`, `VendorExVersion` and `VendorExtensionDesc`.
+    pub fn vendor_extensions(&self) -> VendorExtensions {
+        VendorExtensions::parse(
+            self.VendorExID,
+            self.VendorExVersion,
+            &self.VendorExtensionDesc,
+        )
+    }
+
+    /// The vendor whose extension set the device implements.
+    ///
+    /// Falls back to the manufacturer name for bodies that only report the MTP extension set.
+    pub fn vendor(&self) -> VendorId {
+        let vendor = self.vendor_extensions().vendor();
+        match vendor {
+            VendorId::Microsoft | VendorId::Unknown(_) => {
+                let manufacturer = self.Manufacturer.to_ascii_lowercase();
+                if manufacturer.contains("olympus") || manufacturer.contains("om digital") {
+                    VendorId::Olympus
+                } else if manufacturer.contains("panasonic") {
+                    VendorId::Panasonic
+                } else {
+                    vendor
+                }
+            }
+            _ => vendor,
+        }
+    }
+
+    pub fn supports_op(&self, code: CommandCode) -> bool {
+        self.OperationsSupported.contains(&code)
+    }
+
+    pub fn supports_event(&self, code: EventCode) -> bool {
+        self.EventsSupported.contains(&code)
+    }
+
+    pub fn supports_prop(&self, code: PropCode) -> bool {
+        self.DevicePropertiesSupported.contains(&code)
     }
 }
 
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Clone, PtpDataset)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectInfo {
     pub StorageID: u32,
     pub ObjectFormat: u16,
@@ -235,34 +401,43 @@ pub struct ObjectInfo {
 
 impl ObjectInfo {
     pub fn decode(buf: &[u8]) -> Result<ObjectInfo, Error> {
-        let mut cur = Cursor::new(buf);
-
-        Ok(ObjectInfo {
-            StorageID: cur.read_ptp_u32()?,
-            ObjectFormat: cur.read_ptp_u16()?,
-            ProtectionStatus: cur.read_ptp_u16()?,
-            ObjectCompressedSize: cur.read_ptp_u32()?,
-            ThumbFormat: cur.read_ptp_u16()?,
-            ThumbCompressedSize: cur.read_ptp_u32()?,
-            ThumbPixWidth: cur.read_ptp_u32()?,
-            ThumbPixHeight: cur.read_ptp_u32()?,
-            ImagePixWidth: cur.read_ptp_u32()?,
-            ImagePixHeight: cur.read_ptp_u32()?,
-            ImageBitDepth: cur.read_ptp_u32()?,
-            ParentObject: cur.read_ptp_u32()?,
-            AssociationType: cur.read_ptp_u16()?,
-            AssociationDesc: cur.read_ptp_u32()?,
-            SequenceNumber: cur.read_ptp_u32()?,
-            Filename: cur.read_ptp_str()?,
-            CaptureDate: cur.read_ptp_str()?,
-            ModificationDate: cur.read_ptp_str()?,
-            Keywords: cur.read_ptp_str()?,
-        })
+        ObjectInfo::from_bytes(buf)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    pub fn protection_status(&self) -> ProtectionStatus {
+        ProtectionStatus::from_u16(self.ProtectionStatus)
+    }
+
+    pub fn association_type(&self) -> AssociationType {
+        AssociationType::from_u16(self.AssociationType)
+    }
+
+    /// `CaptureDate`, or `None` if the device left it empty
+    pub fn capture_date(&self) -> Result<Option<PtpDateTime>, Error> {
+        parse_optional_date(&self.CaptureDate)
+    }
+
+    /// `ModificationDate`, or `None` if the device left it empty
+    pub fn modification_date(&self) -> Result<Option<PtpDateTime>, Error> {
+        parse_optional_date(&self.ModificationDate)
+    }
+}
+
+fn parse_optional_date(s: &str) -> Result<Option<PtpDateTime>, Error> {
+    if s.trim_end_matches('\0').is_empty() {
+        Ok(None)
+    } else {
+        PtpDateTime::parse(s).map(Some)
     }
 }
 
 #[allow(non_snake_case)]
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, PtpDataset)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StorageInfo {
     pub StorageType: u16,
     pub FilesystemType: u16,
@@ -276,20 +451,251 @@ pub struct StorageInfo {
 
 impl StorageInfo {
     pub fn decode<T: Read>(cur: &mut T) -> Result<StorageInfo, Error> {
-        Ok(StorageInfo {
-            StorageType: cur.read_ptp_u16()?,
-            FilesystemType: cur.read_ptp_u16()?,
-            AccessCapability: cur.read_ptp_u16()?,
-            MaxCapacity: cur.read_ptp_u64()?,
-            FreeSpaceInBytes: cur.read_ptp_u64()?,
-            FreeSpaceInImages: cur.read_ptp_u32()?,
-            StorageDescription: cur.read_ptp_str()?,
-            VolumeLabel: cur.read_ptp_str()?,
-        })
+        StorageInfo::decode_from(cur)
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
+
+    pub fn storage_type(&self) -> StorageType {
+        StorageType::from_u16(self.StorageType)
+    }
+
+    pub fn filesystem_type(&self) -> FilesystemType {
+        FilesystemType::from_u16(self.FilesystemType)
+    }
+
+    pub fn access_capability(&self) -> AccessCapability {
+        AccessCapability::from_u16(self.AccessCapability)
     }
 }
 
-#[derive(Debug)]
+/// Physical kind of a store, from `StorageInfo::StorageType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StorageType {
+    Undefined,
+    FixedROM,
+    RemovableROM,
+    FixedRAM,
+    RemovableRAM,
+    /// A reserved or vendor-defined value
+    Unknown(u16),
+}
+
+impl StorageType {
+    pub fn from_u16(v: u16) -> StorageType {
+        use self::StorageType::*;
+        match v {
+            0x0000 => Undefined,
+            0x0001 => FixedROM,
+            0x0002 => RemovableROM,
+            0x0003 => FixedRAM,
+            0x0004 => RemovableRAM,
+            v => Unknown(v),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        use self::StorageType::*;
+        match self {
+            Undefined => 0x0000,
+            FixedROM => 0x0001,
+            RemovableROM => 0x0002,
+            FixedRAM => 0x0003,
+            RemovableRAM => 0x0004,
+            Unknown(v) => v,
+        }
+    }
+}
+
+impl From<u16> for StorageType {
+    fn from(v: u16) -> StorageType {
+        StorageType::from_u16(v)
+    }
+}
+
+/// Logical file system of a store, from `StorageInfo::FilesystemType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FilesystemType {
+    Undefined,
+    GenericFlat,
+    GenericHierarchical,
+    /// Design rule for Camera File system
+    DCF,
+    /// A reserved or vendor-defined value
+    Unknown(u16),
+}
+
+impl FilesystemType {
+    pub fn from_u16(v: u16) -> FilesystemType {
+        use self::FilesystemType::*;
+        match v {
+            0x0000 => Undefined,
+            0x0001 => GenericFlat,
+            0x0002 => GenericHierarchical,
+            0x0003 => DCF,
+            v => Unknown(v),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        use self::FilesystemType::*;
+        match self {
+            Undefined => 0x0000,
+            GenericFlat => 0x0001,
+            GenericHierarchical => 0x0002,
+            DCF => 0x0003,
+            Unknown(v) => v,
+        }
+    }
+}
+
+impl From<u16> for FilesystemType {
+    fn from(v: u16) -> FilesystemType {
+        FilesystemType::from_u16(v)
+    }
+}
+
+/// Write access to a store, from `StorageInfo::AccessCapability`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccessCapability {
+    ReadWrite,
+    /// Read-only, objects cannot be deleted
+    ReadOnly,
+    /// Read-only, but objects can be deleted
+    ReadOnlyWithDeletion,
+    /// A reserved or vendor-defined value
+    Unknown(u16),
+}
+
+impl AccessCapability {
+    pub fn from_u16(v: u16) -> AccessCapability {
+        use self::AccessCapability::*;
+        match v {
+            0x0000 => ReadWrite,
+            0x0001 => ReadOnly,
+            0x0002 => ReadOnlyWithDeletion,
+            v => Unknown(v),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        use self::AccessCapability::*;
+        match self {
+            ReadWrite => 0x0000,
+            ReadOnly => 0x0001,
+            ReadOnlyWithDeletion => 0x0002,
+            Unknown(v) => v,
+        }
+    }
+}
+
+impl From<u16> for AccessCapability {
+    fn from(v: u16) -> AccessCapability {
+        AccessCapability::from_u16(v)
+    }
+}
+
+/// Write protection of an object, from `ObjectInfo::ProtectionStatus`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ProtectionStatus {
+    NoProtection,
+    ReadOnly,
+    /// MTP: the object data is read-only, its properties are not
+    MtpReadOnlyData,
+    /// MTP: the object data cannot be read by the initiator
+    MtpNonTransferableData,
+    /// A reserved or vendor-defined value
+    Unknown(u16),
+}
+
+impl ProtectionStatus {
+    pub fn from_u16(v: u16) -> ProtectionStatus {
+        use self::ProtectionStatus::*;
+        match v {
+            0x0000 => NoProtection,
+            0x0001 => ReadOnly,
+            0x8002 => MtpReadOnlyData,
+            0x8003 => MtpNonTransferableData,
+            v => Unknown(v),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        use self::ProtectionStatus::*;
+        match self {
+            NoProtection => 0x0000,
+            ReadOnly => 0x0001,
+            MtpReadOnlyData => 0x8002,
+            MtpNonTransferableData => 0x8003,
+            Unknown(v) => v,
+        }
+    }
+}
+
+impl From<u16> for ProtectionStatus {
+    fn from(v: u16) -> ProtectionStatus {
+        ProtectionStatus::from_u16(v)
+    }
+}
+
+/// Kind of association (folder) object, from `ObjectInfo::AssociationType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssociationType {
+    Undefined,
+    GenericFolder,
+    Album,
+    TimeSequence,
+    HorizontalPanoramic,
+    VerticalPanoramic,
+    /// `2DPanoramic` in the specification
+    Panoramic2D,
+    AncillaryData,
+    /// A reserved or vendor-defined value
+    Unknown(u16),
+}
+
+impl AssociationType {
+    pub fn from_u16(v: u16) -> AssociationType {
+        use self::AssociationType::*;
+        match v {
+            0x0000 => Undefined,
+            0x0001 => GenericFolder,
+            0x0002 => Album,
+            0x0003 => TimeSequence,
+            0x0004 => HorizontalPanoramic,
+            0x0005 => VerticalPanoramic,
+            0x0006 => Panoramic2D,
+            0x0007 => AncillaryData,
+            v => Unknown(v),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        use self::AssociationType::*;
+        match self {
+            Undefined => 0x0000,
+            GenericFolder => 0x0001,
+            Album => 0x0002,
+            TimeSequence => 0x0003,
+            HorizontalPanoramic => 0x0004,
+            VerticalPanoramic => 0x0005,
+            Panoramic2D => 0x0006,
+            AncillaryData => 0x0007,
+            Unknown(v) => v,
+        }
+    }
+}
+
+impl From<u16> for AssociationType {
+    fn from(v: u16) -> AssociationType {
+        AssociationType::from_u16(v)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, PtpDataset)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PropInfo {
     /// A specific property_code.
     pub property_code: u16,
@@ -297,47 +703,50 @@ pub struct PropInfo {
     pub data_type: u16,
     /// This field indicates whether the property is read-only or read-write.
     pub get_set: u8,
+    #[ptp(data_type = "data_type")]
     pub factory_default: DataType,
+    #[ptp(data_type = "data_type")]
     pub current: DataType,
+    #[ptp(data_type = "data_type")]
     pub form: FormData,
 }
 
 impl PropInfo {
     pub fn decode<T: Read>(cur: &mut T) -> Result<PropInfo, Error> {
+        PropInfo::decode_from(cur)
+    }
+
+    /// Like `decode`, but a property of a datatype this crate does not know is returned with
+    /// `UNDEF` values and no form, leaving `cur` at its factory default value.
+    pub fn decode_lenient<T: Read>(cur: &mut T) -> Result<PropInfo, Error> {
         let property_code = cur.read_ptp_u16()?;
         let data_type = cur.read_ptp_u16()?;
+        let get_set = cur.read_ptp_u8()?;
+        let factory_default = DataType::read_type_lenient(data_type, cur)?;
+        let current = DataType::read_type_lenient(data_type, cur)?;
+        let form = if DataType::is_known_type(data_type) {
+            FormData::read_form(data_type, cur)?
+        } else {
+            FormData::None
+        };
+
         Ok(PropInfo {
             property_code,
             data_type,
-            get_set: cur.read_u8()?,
-            factory_default: DataType::read_type(data_type, cur)?,
-            current: DataType::read_type(data_type, cur)?,
-            form: {
-                match cur.read_u8()? {
-                    // 0x00 => FormData::None,
-                    0x01 => FormData::Range {
-                        min_value: DataType::read_type(data_type, cur)?,
-                        max_value: DataType::read_type(data_type, cur)?,
-                        step: DataType::read_type(data_type, cur)?,
-                    },
-                    0x02 => FormData::Enumeration {
-                        array: {
-                            let len = cur.read_u16::<LittleEndian>()? as usize;
-                            let mut arr = Vec::with_capacity(len);
-                            for _ in 0..len {
-                                arr.push(DataType::read_type(data_type, cur)?);
-                            }
-                            arr
-                        },
-                    },
-                    _ => FormData::None,
-                }
-            },
+            get_set,
+            factory_default,
+            current,
+            form,
         })
     }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, PtpDataset)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PropInfoSony {
     /// A specific property_code.
     pub property_code: u16,
@@ -347,48 +756,52 @@ pub struct PropInfoSony {
     pub get_set: u8,
     /// This field indicates whether the property is valid, invalid or DispOnly.
     pub is_enable: u8,
+    #[ptp(data_type = "data_type")]
     pub factory_default: DataType,
+    #[ptp(data_type = "data_type")]
     pub current: DataType,
+    #[ptp(data_type = "data_type")]
     pub form: FormData,
 }
 
 impl PropInfoSony {
     pub fn decode<T: Read>(cur: &mut T) -> Result<PropInfoSony, Error> {
+        PropInfoSony::decode_from(cur)
+    }
+
+    /// Like `decode`, but a property of a datatype this crate does not know is returned with
+    /// `UNDEF` values and no form, leaving `cur` at its factory default value.
+    pub fn decode_lenient<T: Read>(cur: &mut T) -> Result<PropInfoSony, Error> {
         let property_code = cur.read_ptp_u16()?;
         let data_type = cur.read_ptp_u16()?;
+        let get_set = cur.read_ptp_u8()?;
+        let is_enable = cur.read_ptp_u8()?;
+        let factory_default = DataType::read_type_lenient(data_type, cur)?;
+        let current = DataType::read_type_lenient(data_type, cur)?;
+        let form = if DataType::is_known_type(data_type) {
+            FormData::read_form(data_type, cur)?
+        } else {
+            FormData::None
+        };
+
         Ok(PropInfoSony {
             property_code,
             data_type,
-            get_set: cur.read_u8()?,
-            is_enable: cur.read_u8()?,
-            factory_default: DataType::read_type(data_type, cur)?,
-            current: DataType::read_type(data_type, cur)?,
-            form: {
-                match cur.read_u8()? {
-                    // 0x00 => FormData::None,
-                    0x01 => FormData::Range {
-                        min_value: DataType::read_type(data_type, cur)?,
-                        max_value: DataType::read_type(data_type, cur)?,
-                        step: DataType::read_type(data_type, cur)?,
-                    },
-                    0x02 => FormData::Enumeration {
-                        array: {
-                            let len = cur.read_u16::<LittleEndian>()? as usize;
-                            let mut arr = Vec::with_capacity(len);
-                            for _ in 0..len {
-                                arr.push(DataType::read_type(data_type, cur)?);
-                            }
-                            arr
-                        },
-                    },
-                    _ => FormData::None,
-                }
-            },
+            get_set,
+            is_enable,
+            factory_default,
+            current,
+            form,
         })
     }
+
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        self.to_bytes()
+    }
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTree {
     pub handle: u32,
     pub info: ObjectInfo,
@@ -0,0 +1,463 @@
+use super::camera::{ContainerInfo, ContainerType, CONTAINER_INFO_SIZE};
+use super::{CommandCode, Error, PtpEvent, Read, Write};
+use std::{
+    io::{self, Cursor, Read as _, Write as _},
+    net::{Shutdown, SocketAddr, TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+/// TCP port of PTP/IP responders
+pub const PTPIP_PORT: u16 = 15740;
+/// TCP port of the command connection of Fujifilm bodies
+pub const FUJI_PORT: u16 = 55740;
+
+// packet types
+const INIT_COMMAND_REQUEST: u32 = 1;
+const INIT_COMMAND_ACK: u32 = 2;
+const INIT_EVENT_REQUEST: u32 = 3;
+const INIT_EVENT_ACK: u32 = 4;
+const INIT_FAIL: u32 = 5;
+const OPERATION_REQUEST: u32 = 6;
+const OPERATION_RESPONSE: u32 = 7;
+const EVENT: u32 = 8;
+const START_DATA: u32 = 9;
+const DATA: u32 = 10;
+const END_DATA: u32 = 12;
+
+const PACKET_HEADER_SIZE: usize = 8;
+const PROTOCOL_VERSION: u32 = 0x0001_0000;
+
+// data phase field of OperationRequest packets
+const DATA_PHASE_NONE_OR_IN: u32 = 1;
+const DATA_PHASE_OUT: u32 = 2;
+
+// Fuji's InitCommandRequest carries this in place of the protocol version, and a fixed-size name
+const FUJI_PROTOCOL_VERSION: u32 = 0x8F53_E4F2;
+const FUJI_NAME_SIZE: usize = 54;
+
+/// Connection handshake and framing spoken by a PTP/IP responder
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtpIpHandshake {
+    /// CIPA DC-005: command and event connections each opened with an init request, and
+    /// transactions carried in PTP/IP packets
+    Standard,
+    /// Fujifilm X bodies: a vendor InitCommandRequest, then USB-style containers on the command
+    /// connection. Events arrive as containers on a second connection opened without a handshake.
+    Fuji,
+}
+
+/// How to connect to a PTP/IP responder
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtpIpOptions {
+    /// Identifies this initiator to the responder, which may ask the user to pair with it
+    pub guid: [u8; 16],
+    /// Initiator name shown by the responder
+    pub name: String,
+    pub handshake: PtpIpHandshake,
+    /// Port of the event connection. Defaults to the command port for the standard handshake
+    /// and to the next port for Fuji's.
+    pub event_port: Option<u16>,
+}
+
+impl Default for PtpIpOptions {
+    fn default() -> PtpIpOptions {
+        PtpIpOptions {
+            guid: *b"rust-ptp-client\0",
+            name: "rust-ptp".to_owned(),
+            handshake: PtpIpHandshake::Standard,
+            event_port: None,
+        }
+    }
+}
+
+/// The responder's half of the standard handshake
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PtpIpResponder {
+    pub connection_number: u32,
+    pub guid: [u8; 16],
+    pub name: String,
+    pub protocol_version: u32,
+}
+
+pub(crate) struct PtpIpTransport {
+    handshake: PtpIpHandshake,
+    command: TcpStream,
+    event: Option<TcpStream>,
+    event_addr: SocketAddr,
+    responder: Option<PtpIpResponder>,
+    // code of the current transaction, reported for its data phase
+    code: CommandCode,
+}
+
+// a timeout of zero means no timeout, as for USB transfers
+fn socket_timeout(timeout: Duration) -> Option<Duration> {
+    if timeout == Duration::default() {
+        None
+    } else {
+        Some(timeout)
+    }
+}
+
+fn connect(addr: SocketAddr, timeout: Duration) -> Result<TcpStream, Error> {
+    let stream = match socket_timeout(timeout) {
+        Some(timeout) => TcpStream::connect_timeout(&addr, timeout)?,
+        None => TcpStream::connect(addr)?,
+    };
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(socket_timeout(timeout))?;
+    stream.set_write_timeout(socket_timeout(timeout))?;
+    Ok(stream)
+}
+
+fn write_packet(stream: &mut TcpStream, kind: u32, payload: &[u8]) -> Result<(), Error> {
+    let mut buf = Vec::with_capacity(PACKET_HEADER_SIZE + payload.len());
+    buf.write_ptp_u32((PACKET_HEADER_SIZE + payload.len()) as u32)?;
+    buf.write_ptp_u32(kind)?;
+    buf.extend_from_slice(payload);
+    stream.write_all(&buf)?;
+    Ok(())
+}
+
+// read `len` bytes, growing the buffer as they arrive rather than trusting `len` up front
+fn read_payload(stream: &mut TcpStream, len: usize) -> Result<Vec<u8>, Error> {
+    let mut payload = vec![];
+    stream.take(len as u64).read_to_end(&mut payload)?;
+    if payload.len() < len {
+        return Err(Error::Malformed("Unexpected end of message".to_string()));
+    }
+    Ok(payload)
+}
+
+fn read_packet(stream: &mut TcpStream) -> Result<(u32, Vec<u8>), Error> {
+    let mut header = [0; PACKET_HEADER_SIZE];
+    stream.read_exact(&mut header)?;
+    let mut cur = Cursor::new(&header[..]);
+    let len = cur.read_ptp_u32()? as usize;
+    let kind = cur.read_ptp_u32()?;
+    let payload_len = len.checked_sub(PACKET_HEADER_SIZE).ok_or_else(|| {
+        Error::Malformed(format!(
+            "PTP/IP packet length {} is shorter than its header",
+            len
+        ))
+    })?;
+    Ok((kind, read_payload(stream, payload_len)?))
+}
+
+fn init_failed(payload: &[u8]) -> Error {
+    let reason = Cursor::new(payload).read_ptp_u32().unwrap_or(0);
+    Error::Io(io::Error::new(
+        io::ErrorKind::ConnectionRefused,
+        format!(
+            "PTP/IP responder refused the connection (reason 0x{:08x})",
+            reason
+        ),
+    ))
+}
+
+fn unexpected_packet(kind: u32) -> Error {
+    Error::Malformed(format!("Unexpected PTP/IP packet type {}", kind))
+}
+
+// NUL-terminated UTF-16, as used by the init packets
+fn write_utf16z(buf: &mut Vec<u8>, s: &str) -> Result<(), Error> {
+    for unit in s.encode_utf16().chain(Some(0)) {
+        buf.write_ptp_u16(unit)?;
+    }
+    Ok(())
+}
+
+fn read_utf16z<R: Read>(r: &mut R) -> Result<String, Error> {
+    let mut units = vec![];
+    loop {
+        match r.read_ptp_u16()? {
+            0 => break,
+            unit => units.push(unit),
+        }
+    }
+    String::from_utf16(&units).map_err(|_| Error::Malformed("Invalid UTF16 data".to_string()))
+}
+
+fn container(kind: ContainerType, code: u16, tid: u32, payload: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut buf = Vec::with_capacity(CONTAINER_INFO_SIZE + payload.len());
+    buf.write_ptp_u32((CONTAINER_INFO_SIZE + payload.len()) as u32)?;
+    buf.write_ptp_u16(kind as u16)?;
+    buf.write_ptp_u16(code)?;
+    buf.write_ptp_u32(tid)?;
+    buf.extend_from_slice(payload);
+    Ok(buf)
+}
+
+fn read_container(stream: &mut TcpStream) -> Result<(ContainerInfo, Vec<u8>), Error> {
+    let mut header = [0; CONTAINER_INFO_SIZE];
+    stream.read_exact(&mut header)?;
+    let cinfo = ContainerInfo::parse(&header[..])?;
+    let payload = read_payload(stream, cinfo.payload_len)?;
+    Ok((cinfo, payload))
+}
+
+fn is_timeout(err: &Error) -> bool {
+    match *err {
+        Error::Io(ref e) => {
+            e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut
+        }
+        _ => false,
+    }
+}
+
+impl PtpIpTransport {
+    pub fn connect<A: ToSocketAddrs>(
+        addr: A,
+        options: &PtpIpOptions,
+        timeout: Duration,
+    ) -> Result<PtpIpTransport, Error> {
+        let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+            Error::Io(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no address to connect to",
+            ))
+        })?;
+        let mut event_addr = addr;
+        event_addr.set_port(match (options.event_port, options.handshake) {
+            (Some(port), _) => port,
+            (None, PtpIpHandshake::Standard) => addr.port(),
+            (None, PtpIpHandshake::Fuji) => addr.port().checked_add(1).ok_or_else(|| {
+                Error::Io(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "no event port after command port 65535, set one in PtpIpOptions",
+                ))
+            })?,
+        });
+
+        let mut transport = PtpIpTransport {
+            handshake: options.handshake,
+            command: connect(addr, timeout)?,
+            event: None,
+            event_addr,
+            responder: None,
+            code: 0,
+        };
+        match options.handshake {
+            PtpIpHandshake::Standard => transport.init_standard(options, timeout)?,
+            PtpIpHandshake::Fuji => transport.init_fuji(options)?,
+        }
+        Ok(transport)
+    }
+
+    fn init_standard(&mut self, options: &PtpIpOptions, timeout: Duration) -> Result<(), Error> {
+        let mut request = options.guid.to_vec();
+        write_utf16z(&mut request, &options.name)?;
+        request.write_ptp_u32(PROTOCOL_VERSION)?;
+        write_packet(&mut self.command, INIT_COMMAND_REQUEST, &request)?;
+
+        let responder = match read_packet(&mut self.command)? {
+            (INIT_COMMAND_ACK, payload) => {
+                let mut cur = Cursor::new(&payload[..]);
+                let connection_number = cur.read_ptp_u32()?;
+                let mut guid = [0; 16];
+                cur.read_exact(&mut guid)?;
+                let name = read_utf16z(&mut cur)?;
+                let protocol_version = cur.read_ptp_u32()?;
+                PtpIpResponder {
+                    connection_number,
+                    guid,
+                    name,
+                    protocol_version,
+                }
+            }
+            (INIT_FAIL, payload) => return Err(init_failed(&payload)),
+            (kind, _) => return Err(unexpected_packet(kind)),
+        };
+        debug!("PTP/IP responder {:?}", responder);
+
+        let mut event = connect(self.event_addr, timeout)?;
+        write_packet(
+            &mut event,
+            INIT_EVENT_REQUEST,
+            &responder.connection_number.to_le_bytes(),
+        )?;
+        match read_packet(&mut event)? {
+            (INIT_EVENT_ACK, _) => {}
+            (INIT_FAIL, payload) => return Err(init_failed(&payload)),
+            (kind, _) => return Err(unexpected_packet(kind)),
+        }
+
+        self.event = Some(event);
+        self.responder = Some(responder);
+        Ok(())
+    }
+
+    fn init_fuji(&mut self, options: &PtpIpOptions) -> Result<(), Error> {
+        let mut request = vec![];
+        request.write_ptp_u32(FUJI_PROTOCOL_VERSION)?;
+        request.extend_from_slice(&options.guid);
+        let mut name = vec![];
+        write_utf16z(&mut name, &options.name)?;
+        name.resize(FUJI_NAME_SIZE, 0);
+        // keep the terminator if the name had to be cut
+        name[FUJI_NAME_SIZE - 2..].copy_from_slice(&[0, 0]);
+        request.extend_from_slice(&name);
+        write_packet(&mut self.command, INIT_COMMAND_REQUEST, &request)?;
+
+        match read_packet(&mut self.command)? {
+            (INIT_COMMAND_ACK, _) => Ok(()),
+            (INIT_FAIL, payload) => Err(init_failed(&payload)),
+            (kind, _) => Err(unexpected_packet(kind)),
+        }
+    }
+
+    /// The responder's half of the standard handshake
+    pub fn responder(&self) -> Option<&PtpIpResponder> {
+        self.responder.as_ref()
+    }
+
+    fn set_timeout(&self, timeout: Duration) -> Result<(), Error> {
+        self.command.set_read_timeout(socket_timeout(timeout))?;
+        self.command.set_write_timeout(socket_timeout(timeout))?;
+        Ok(())
+    }
+
+    /// Send the request phase of a transaction, and its data phase if there is one.
+    pub fn write_request(
+        &mut self,
+        code: CommandCode,
+        tid: u32,
+        params: &[u8],
+        data: Option<&[u8]>,
+        timeout: Duration,
+    ) -> Result<(), Error> {
+        self.set_timeout(timeout)?;
+        self.code = code;
+
+        if self.handshake == PtpIpHandshake::Fuji {
+            let mut buf = container(ContainerType::Command, code, tid, params)?;
+            if let Some(data) = data {
+                buf.extend(container(ContainerType::Data, code, tid, data)?);
+            }
+            self.command.write_all(&buf)?;
+            return Ok(());
+        }
+
+        let mut request = vec![];
+        let data_phase = if data.is_some() {
+            DATA_PHASE_OUT
+        } else {
+            DATA_PHASE_NONE_OR_IN
+        };
+        request.write_ptp_u32(data_phase)?;
+        request.write_ptp_u16(code)?;
+        request.write_ptp_u32(tid)?;
+        request.extend_from_slice(params);
+        write_packet(&mut self.command, OPERATION_REQUEST, &request)?;
+
+        if let Some(data) = data {
+            let mut start = vec![];
+            start.write_ptp_u32(tid)?;
+            start.write_ptp_u64(data.len() as u64)?;
+            write_packet(&mut self.command, START_DATA, &start)?;
+
+            let mut end = tid.to_le_bytes().to_vec();
+            end.extend_from_slice(data);
+            write_packet(&mut self.command, END_DATA, &end)?;
+        }
+        Ok(())
+    }
+
+    /// Read the next data or response phase.
+    pub fn read_phase(&mut self, timeout: Duration) -> Result<(ContainerInfo, Vec<u8>), Error> {
+        self.set_timeout(timeout)?;
+
+        if self.handshake == PtpIpHandshake::Fuji {
+            return read_container(&mut self.command);
+        }
+
+        match read_packet(&mut self.command)? {
+            (OPERATION_RESPONSE, payload) => {
+                let mut cur = Cursor::new(&payload[..]);
+                let code = cur.read_ptp_u16()?;
+                let tid = cur.read_ptp_u32()?;
+                let params = payload[6..].to_vec();
+                Ok((
+                    ContainerInfo {
+                        payload_len: params.len(),
+                        kind: ContainerType::Response,
+                        code,
+                        tid,
+                    },
+                    params,
+                ))
+            }
+            (START_DATA, payload) => {
+                let tid = Cursor::new(&payload[..]).read_ptp_u32()?;
+                let mut data = vec![];
+                loop {
+                    let (kind, payload) = read_packet(&mut self.command)?;
+                    if kind != DATA && kind != END_DATA {
+                        return Err(unexpected_packet(kind));
+                    }
+                    if Cursor::new(&payload[..]).read_ptp_u32()? != tid {
+                        return Err(Error::Malformed(format!(
+                            "PTP/IP data packet outside transaction {}",
+                            tid
+                        )));
+                    }
+                    data.extend_from_slice(&payload[4..]);
+                    if kind == END_DATA {
+                        break;
+                    }
+                }
+                Ok((
+                    ContainerInfo {
+                        payload_len: data.len(),
+                        kind: ContainerType::Data,
+                        code: self.code,
+                        tid,
+                    },
+                    data,
+                ))
+            }
+            (kind, _) => Err(unexpected_packet(kind)),
+        }
+    }
+
+    /// Wait for an event on the event connection, returning `None` on timeout.
+    pub fn read_event(&mut self, timeout: Duration) -> Result<Option<PtpEvent>, Error> {
+        if self.event.is_none() {
+            // only Fuji's event connection is opened on demand
+            self.event = Some(connect(self.event_addr, timeout)?);
+        }
+        let event = self.event.as_mut().unwrap();
+        event.set_read_timeout(socket_timeout(timeout))?;
+
+        let res = match self.handshake {
+            PtpIpHandshake::Standard => read_packet(event).and_then(|packet| match packet {
+                (EVENT, payload) => {
+                    let mut cur = Cursor::new(&payload[..]);
+                    let code = cur.read_ptp_u16()?;
+                    let tid = cur.read_ptp_u32()?;
+                    let params = (0..(payload.len() - 6) / 4)
+                        .map(|_| cur.read_ptp_u32())
+                        .collect::<Result<_, _>>()?;
+                    PtpEvent::from_parts(code, tid, params)
+                }
+                (kind, _) => Err(unexpected_packet(kind)),
+            }),
+            PtpIpHandshake::Fuji => read_container(event).and_then(|(cinfo, payload)| {
+                PtpEvent::decode(&container(cinfo.kind, cinfo.code, cinfo.tid, &payload)?)
+            }),
+        };
+
+        match res {
+            Ok(event) => Ok(Some(event)),
+            Err(ref e) if is_timeout(e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn disconnect(&mut self) -> Result<(), Error> {
+        if let Some(event) = self.event.take() {
+            event.shutdown(Shutdown::Both)?;
+        }
+        self.command.shutdown(Shutdown::Both)?;
+        Ok(())
+    }
+}
@@ -1,7 +1,22 @@
 use super::Error;
 use byteorder::{LittleEndian, ReadBytesExt};
-use std::io::Cursor;
+use std::{
+    cmp::{max, min},
+    io::{self, Cursor},
+    mem::size_of,
+};
 
+/// Largest array length accepted when decoding, whatever the size of the input
+pub const MAX_ARRAY_LEN: usize = 1 << 24;
+
+// arrays longer than this grow as they are read, so a bogus length cannot trigger an
+// allocation larger than the input actually holds
+pub(crate) const MAX_PREALLOC_LEN: usize = 4096;
+
+/// Reading of PTP primitives and arrays, implemented for every `io::Read`.
+///
+/// A bogus array length fails once the input runs out. Wrap a stream in `LimitedReader` so
+/// that this happens at the end of the payload rather than further into the stream.
 pub trait Read: ReadBytesExt {
     fn read_ptp_u8(&mut self) -> Result<u8, Error> {
         Ok(self.read_u8()?)
@@ -43,53 +58,82 @@ pub trait Read: ReadBytesExt {
         Ok(self.read_i128::<LittleEndian>()?)
     }
 
+    /// Check a length read from the input before reading `len` array elements.
+    fn check_array_len(&self, len: usize) -> Result<(), Error> {
+        if len > MAX_ARRAY_LEN {
+            Err(Error::Malformed(format!(
+                "Array of {} elements exceeds the limit of {}",
+                len, MAX_ARRAY_LEN
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read an array of elements encoded in at least one byte each.
     #[inline(always)]
     fn read_ptp_vec<T: Sized, U: Fn(&mut Self) -> Result<T, Error>>(
         &mut self,
         func: U,
+    ) -> Result<Vec<T>, Error> {
+        self.read_ptp_vec_sized(1, func)
+    }
+
+    /// Read an array of elements encoded in at least `min_size` bytes each, allocating up
+    /// front no more elements than `MAX_PREALLOC_LEN` bytes of input could hold.
+    #[inline(always)]
+    fn read_ptp_vec_sized<T: Sized, U: Fn(&mut Self) -> Result<T, Error>>(
+        &mut self,
+        min_size: usize,
+        func: U,
     ) -> Result<Vec<T>, Error> {
         let len = self.read_u32::<LittleEndian>()? as usize;
-        (0..len).map(|_| func(self)).collect()
+        self.check_array_len(len)?;
+        let mut out = Vec::with_capacity(min(len, MAX_PREALLOC_LEN / max(min_size, 1)));
+        for _ in 0..len {
+            out.push(func(self)?);
+        }
+        Ok(out)
     }
 
     fn read_ptp_u8_vec(&mut self) -> Result<Vec<u8>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_u8())
+        self.read_ptp_vec_sized(size_of::<u8>(), |cur| cur.read_ptp_u8())
     }
 
     fn read_ptp_i8_vec(&mut self) -> Result<Vec<i8>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_i8())
+        self.read_ptp_vec_sized(size_of::<i8>(), |cur| cur.read_ptp_i8())
     }
 
     fn read_ptp_u16_vec(&mut self) -> Result<Vec<u16>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_u16())
+        self.read_ptp_vec_sized(size_of::<u16>(), |cur| cur.read_ptp_u16())
     }
 
     fn read_ptp_i16_vec(&mut self) -> Result<Vec<i16>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_i16())
+        self.read_ptp_vec_sized(size_of::<i16>(), |cur| cur.read_ptp_i16())
     }
 
     fn read_ptp_u32_vec(&mut self) -> Result<Vec<u32>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_u32())
+        self.read_ptp_vec_sized(size_of::<u32>(), |cur| cur.read_ptp_u32())
     }
 
     fn read_ptp_i32_vec(&mut self) -> Result<Vec<i32>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_i32())
+        self.read_ptp_vec_sized(size_of::<i32>(), |cur| cur.read_ptp_i32())
     }
 
     fn read_ptp_u64_vec(&mut self) -> Result<Vec<u64>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_u64())
+        self.read_ptp_vec_sized(size_of::<u64>(), |cur| cur.read_ptp_u64())
     }
 
     fn read_ptp_i64_vec(&mut self) -> Result<Vec<i64>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_i64())
+        self.read_ptp_vec_sized(size_of::<i64>(), |cur| cur.read_ptp_i64())
     }
 
     fn read_ptp_u128_vec(&mut self) -> Result<Vec<u128>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_u128())
+        self.read_ptp_vec_sized(size_of::<u128>(), |cur| cur.read_ptp_u128())
     }
 
     fn read_ptp_i128_vec(&mut self) -> Result<Vec<i128>, Error> {
-        self.read_ptp_vec(|cur| cur.read_ptp_i128())
+        self.read_ptp_vec_sized(size_of::<i128>(), |cur| cur.read_ptp_i128())
     }
 
     fn read_ptp_str(&mut self) -> Result<String, Error> {
@@ -106,12 +150,30 @@ pub trait Read: ReadBytesExt {
             Ok("".into())
         }
     }
+}
+
+impl<R: io::Read + ?Sized> Read for R {}
+
+/// Position-dependent checks, for readers over a payload of known length
+pub trait ReadEnd {
+    /// Number of bytes left to read
+    fn remaining_len(&self) -> u64;
 
-    fn expect_end(&mut self) -> Result<(), Error>;
+    /// Fail unless the whole payload was read.
+    fn expect_end(&self) -> Result<(), Error> {
+        match self.remaining_len() {
+            0 => Ok(()),
+            n => Err(Error::Malformed(format!("{} unexpected trailing bytes", n))),
+        }
+    }
 }
 
-impl<T: AsRef<[u8]>> Read for Cursor<T> {
-    fn expect_end(&mut self) -> Result<(), Error> {
+impl<T: AsRef<[u8]>> ReadEnd for Cursor<T> {
+    fn remaining_len(&self) -> u64 {
+        (self.get_ref().as_ref().len() as u64).saturating_sub(self.position())
+    }
+
+    fn expect_end(&self) -> Result<(), Error> {
         let len = self.get_ref().as_ref().len();
         if len as u64 != self.position() {
             Err(Error::Malformed(format!(
@@ -124,3 +186,147 @@ impl<T: AsRef<[u8]>> Read for Cursor<T> {
         }
     }
 }
+
+/// Counts the bytes read from a stream
+#[derive(Debug)]
+pub struct CountingReader<R> {
+    inner: R,
+    count: u64,
+    at_end: bool,
+}
+
+impl<R: io::Read> CountingReader<R> {
+    pub fn new(inner: R) -> CountingReader<R> {
+        CountingReader {
+            inner,
+            count: 0,
+            at_end: false,
+        }
+    }
+
+    /// Number of bytes read so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Whether a read found the stream at its end
+    pub fn at_end(&self) -> bool {
+        self.at_end
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> io::Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.count += n as u64;
+        self.at_end |= n == 0 && !buf.is_empty();
+        Ok(n)
+    }
+}
+
+/// Reads at most `limit` bytes from a stream, such as the payload of a data phase
+#[derive(Debug)]
+pub struct LimitedReader<R> {
+    inner: R,
+    limit: u64,
+    count: u64,
+}
+
+impl<R: io::Read> LimitedReader<R> {
+    pub fn new(inner: R, limit: u64) -> LimitedReader<R> {
+        LimitedReader {
+            inner,
+            limit,
+            count: 0,
+        }
+    }
+
+    /// Number of bytes read so far
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: io::Read> io::Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let max = min(buf.len() as u64, self.limit - self.count) as usize;
+        let n = self.inner.read(&mut buf[..max])?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    // fail without consuming anything when the payload cannot hold `buf`
+    fn read_exact(&mut self, mut buf: &mut [u8]) -> io::Result<()> {
+        if buf.len() as u64 > self.remaining_len() {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        while !buf.is_empty() {
+            match io::Read::read(self, buf) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => buf = &mut buf[n..],
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R> ReadEnd for LimitedReader<R> {
+    fn remaining_len(&self) -> u64 {
+        self.limit - self.count
+    }
+}
+
+/// A dataset that can be decoded from the PTP wire format
+pub trait PtpDecode: Sized {
+    /// Smallest number of bytes a value is encoded in, to bound allocations for arrays of it
+    const MIN_ENCODED_LEN: usize = 0;
+
+    fn decode_from<R: Read>(r: &mut R) -> Result<Self, Error>;
+
+    /// Decode from the start of `buf`, ignoring any trailing data.
+    fn from_bytes(buf: &[u8]) -> Result<Self, Error> {
+        Self::decode_from(&mut Cursor::new(buf))
+    }
+}
+
+macro_rules! impl_decode {
+    ($($ty:ty => $read:ident),*) => {
+        $(impl PtpDecode for $ty {
+            const MIN_ENCODED_LEN: usize = size_of::<$ty>();
+
+            fn decode_from<R: Read>(r: &mut R) -> Result<$ty, Error> {
+                r.$read()
+            }
+        })*
+    };
+}
+
+impl_decode!(u8 => read_ptp_u8, i8 => read_ptp_i8, u16 => read_ptp_u16, i16 => read_ptp_i16, u32 => read_ptp_u32, i32 => read_ptp_i32, u64 => read_ptp_u64, i64 => read_ptp_i64, u128 => read_ptp_u128, i128 => read_ptp_i128);
+
+impl PtpDecode for String {
+    // the length byte
+    const MIN_ENCODED_LEN: usize = 1;
+
+    fn decode_from<R: Read>(r: &mut R) -> Result<String, Error> {
+        r.read_ptp_str()
+    }
+}
+
+impl<T: PtpDecode> PtpDecode for Vec<T> {
+    // the element count
+    const MIN_ENCODED_LEN: usize = 4;
+
+    fn decode_from<R: Read>(r: &mut R) -> Result<Vec<T>, Error> {
+        r.read_ptp_vec_sized(T::MIN_ENCODED_LEN, T::decode_from)
+    }
+}
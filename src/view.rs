@@ -0,0 +1,253 @@
+use super::{AssociationType, Error, ObjectInfo, ProtectionStatus};
+use std::{char, fmt};
+
+/// A PTP string borrowed from a dataset, decoded on demand
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PtpStr<'a> {
+    /// UTF-16LE code units, without the terminator
+    data: &'a [u8],
+}
+
+impl<'a> PtpStr<'a> {
+    /// Split a PTP string off the start of `buf`, returning it and the rest of `buf`.
+    pub fn parse(buf: &'a [u8]) -> Result<(PtpStr<'a>, &'a [u8]), Error> {
+        let eof = || Error::Malformed("Unexpected end of message".to_string());
+        let (&count, rest) = buf.split_first().ok_or_else(eof)?;
+        if count == 0 {
+            return Ok((PtpStr { data: &[] }, rest));
+        }
+        // count includes the trailing null u16
+        let len = usize::from(count) * 2;
+        if rest.len() < len {
+            return Err(eof());
+        }
+        Ok((
+            PtpStr {
+                data: &rest[..len - 2],
+            },
+            &rest[len..],
+        ))
+    }
+
+    /// Length in UTF-16 code units
+    pub fn len(&self) -> usize {
+        self.data.len() / 2
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// The UTF-16LE encoded string, without the terminator
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.data
+    }
+
+    pub fn code_units(&self) -> impl Iterator<Item = u16> + 'a {
+        self.data
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = Result<char, Error>> + 'a {
+        char::decode_utf16(self.code_units())
+            .map(|c| c.map_err(|_| Error::Malformed("Invalid UTF16 data".to_string())))
+    }
+
+    pub fn decode(&self) -> Result<String, Error> {
+        self.chars().collect()
+    }
+
+    pub fn to_string_lossy(&self) -> String {
+        char::decode_utf16(self.code_units())
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+            .collect()
+    }
+
+    /// Compare with `s` without allocating.
+    pub fn eq_str(&self, s: &str) -> bool {
+        self.code_units().eq(s.encode_utf16())
+    }
+}
+
+impl<'a> fmt::Debug for PtpStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl<'a> fmt::Display for PtpStr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.to_string_lossy(), f)
+    }
+}
+
+impl<'a> PartialEq<str> for PtpStr<'a> {
+    fn eq(&self, other: &str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+impl<'a, 'b> PartialEq<&'b str> for PtpStr<'a> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.eq_str(other)
+    }
+}
+
+// size of the fixed-size fields at the start of an ObjectInfo dataset
+const OBJECT_INFO_FIXED_LEN: usize = 52;
+
+/// A view of an `ObjectInfo` dataset that reads fields from the buffer as they are accessed
+#[derive(Clone, Copy)]
+pub struct ObjectInfoRef<'a> {
+    fixed: &'a [u8],
+    filename: PtpStr<'a>,
+    capture_date: PtpStr<'a>,
+    modification_date: PtpStr<'a>,
+    keywords: PtpStr<'a>,
+}
+
+impl<'a> ObjectInfoRef<'a> {
+    /// Check the layout of the dataset in `buf`, ignoring any trailing data.
+    pub fn parse(buf: &'a [u8]) -> Result<ObjectInfoRef<'a>, Error> {
+        if buf.len() < OBJECT_INFO_FIXED_LEN {
+            return Err(Error::Malformed("Unexpected end of message".to_string()));
+        }
+        let (fixed, rest) = buf.split_at(OBJECT_INFO_FIXED_LEN);
+        let (filename, rest) = PtpStr::parse(rest)?;
+        let (capture_date, rest) = PtpStr::parse(rest)?;
+        let (modification_date, rest) = PtpStr::parse(rest)?;
+        let (keywords, _) = PtpStr::parse(rest)?;
+
+        Ok(ObjectInfoRef {
+            fixed,
+            filename,
+            capture_date,
+            modification_date,
+            keywords,
+        })
+    }
+
+    fn u16_at(&self, offset: usize) -> u16 {
+        u16::from_le_bytes([self.fixed[offset], self.fixed[offset + 1]])
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        let b = &self.fixed[offset..offset + 4];
+        u32::from_le_bytes([b[0], b[1], b[2], b[3]])
+    }
+
+    pub fn storage_id(&self) -> u32 {
+        self.u32_at(0)
+    }
+
+    pub fn object_format(&self) -> u16 {
+        self.u16_at(4)
+    }
+
+    pub fn protection_status(&self) -> ProtectionStatus {
+        ProtectionStatus::from_u16(self.u16_at(6))
+    }
+
+    pub fn object_compressed_size(&self) -> u32 {
+        self.u32_at(8)
+    }
+
+    pub fn thumb_format(&self) -> u16 {
+        self.u16_at(12)
+    }
+
+    pub fn thumb_compressed_size(&self) -> u32 {
+        self.u32_at(14)
+    }
+
+    pub fn thumb_pix_width(&self) -> u32 {
+        self.u32_at(18)
+    }
+
+    pub fn thumb_pix_height(&self) -> u32 {
+        self.u32_at(22)
+    }
+
+    pub fn image_pix_width(&self) -> u32 {
+        self.u32_at(26)
+    }
+
+    pub fn image_pix_height(&self) -> u32 {
+        self.u32_at(30)
+    }
+
+    pub fn image_bit_depth(&self) -> u32 {
+        self.u32_at(34)
+    }
+
+    pub fn parent_object(&self) -> u32 {
+        self.u32_at(38)
+    }
+
+    pub fn association_type(&self) -> AssociationType {
+        AssociationType::from_u16(self.u16_at(42))
+    }
+
+    pub fn association_desc(&self) -> u32 {
+        self.u32_at(44)
+    }
+
+    pub fn sequence_number(&self) -> u32 {
+        self.u32_at(48)
+    }
+
+    pub fn filename(&self) -> PtpStr<'a> {
+        self.filename
+    }
+
+    pub fn capture_date(&self) -> PtpStr<'a> {
+        self.capture_date
+    }
+
+    pub fn modification_date(&self) -> PtpStr<'a> {
+        self.modification_date
+    }
+
+    pub fn keywords(&self) -> PtpStr<'a> {
+        self.keywords
+    }
+
+    /// Decode all fields into an owned `ObjectInfo`.
+    pub fn to_object_info(&self) -> Result<ObjectInfo, Error> {
+        Ok(ObjectInfo {
+            StorageID: self.storage_id(),
+            ObjectFormat: self.object_format(),
+            ProtectionStatus: self.u16_at(6),
+            ObjectCompressedSize: self.object_compressed_size(),
+            ThumbFormat: self.thumb_format(),
+            ThumbCompressedSize: self.thumb_compressed_size(),
+            ThumbPixWidth: self.thumb_pix_width(),
+            ThumbPixHeight: self.thumb_pix_height(),
+            ImagePixWidth: self.image_pix_width(),
+            ImagePixHeight: self.image_pix_height(),
+            ImageBitDepth: self.image_bit_depth(),
+            ParentObject: self.parent_object(),
+            AssociationType: self.u16_at(42),
+            AssociationDesc: self.association_desc(),
+            SequenceNumber: self.sequence_number(),
+            Filename: self.filename.decode()?,
+            CaptureDate: self.capture_date.decode()?,
+            ModificationDate: self.modification_date.decode()?,
+            Keywords: self.keywords.decode()?,
+        })
+    }
+}
+
+impl<'a> fmt::Debug for ObjectInfoRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ObjectInfoRef")
+            .field("storage_id", &self.storage_id())
+            .field("object_format", &self.object_format())
+            .field("object_compressed_size", &self.object_compressed_size())
+            .field("parent_object", &self.parent_object())
+            .field("filename", &self.filename)
+            .field("capture_date", &self.capture_date)
+            .finish()
+    }
+}
@@ -0,0 +1,156 @@
+use super::Error;
+use byteorder::{LittleEndian, WriteBytesExt};
+
+pub trait Write: WriteBytesExt {
+    fn write_ptp_u8(&mut self, v: u8) -> Result<(), Error> {
+        Ok(self.write_u8(v)?)
+    }
+
+    fn write_ptp_i8(&mut self, v: i8) -> Result<(), Error> {
+        Ok(self.write_i8(v)?)
+    }
+
+    fn write_ptp_u16(&mut self, v: u16) -> Result<(), Error> {
+        Ok(self.write_u16::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_i16(&mut self, v: i16) -> Result<(), Error> {
+        Ok(self.write_i16::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_u32(&mut self, v: u32) -> Result<(), Error> {
+        Ok(self.write_u32::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_i32(&mut self, v: i32) -> Result<(), Error> {
+        Ok(self.write_i32::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_u64(&mut self, v: u64) -> Result<(), Error> {
+        Ok(self.write_u64::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_i64(&mut self, v: i64) -> Result<(), Error> {
+        Ok(self.write_i64::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_u128(&mut self, v: u128) -> Result<(), Error> {
+        Ok(self.write_u128::<LittleEndian>(v)?)
+    }
+
+    fn write_ptp_i128(&mut self, v: i128) -> Result<(), Error> {
+        Ok(self.write_i128::<LittleEndian>(v)?)
+    }
+
+    #[inline(always)]
+    fn write_ptp_vec<T, U: Fn(&mut Self, &T) -> Result<(), Error>>(
+        &mut self,
+        items: &[T],
+        func: U,
+    ) -> Result<(), Error> {
+        if items.len() > u32::MAX as usize {
+            return Err(Error::Malformed(format!(
+                "Array of {} elements exceeds the PTP limit",
+                items.len()
+            )));
+        }
+        self.write_u32::<LittleEndian>(items.len() as u32)?;
+        items.iter().try_for_each(|item| func(self, item))
+    }
+
+    fn write_ptp_u8_vec(&mut self, v: &[u8]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_u8(item))
+    }
+
+    fn write_ptp_i8_vec(&mut self, v: &[i8]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_i8(item))
+    }
+
+    fn write_ptp_u16_vec(&mut self, v: &[u16]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_u16(item))
+    }
+
+    fn write_ptp_i16_vec(&mut self, v: &[i16]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_i16(item))
+    }
+
+    fn write_ptp_u32_vec(&mut self, v: &[u32]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_u32(item))
+    }
+
+    fn write_ptp_i32_vec(&mut self, v: &[i32]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_i32(item))
+    }
+
+    fn write_ptp_u64_vec(&mut self, v: &[u64]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_u64(item))
+    }
+
+    fn write_ptp_i64_vec(&mut self, v: &[i64]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_i64(item))
+    }
+
+    fn write_ptp_u128_vec(&mut self, v: &[u128]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_u128(item))
+    }
+
+    fn write_ptp_i128_vec(&mut self, v: &[i128]) -> Result<(), Error> {
+        self.write_ptp_vec(v, |cur, &item| cur.write_ptp_i128(item))
+    }
+
+    fn write_ptp_str(&mut self, v: &str) -> Result<(), Error> {
+        if v.is_empty() {
+            return Ok(self.write_u8(0)?);
+        }
+        // the count is in UTF-16 code units and includes the trailing null
+        let data: Vec<u16> = v.encode_utf16().collect();
+        if data.len() > 254 {
+            return Err(Error::Malformed(format!(
+                "String of {} UTF-16 code units exceeds the PTP limit of 254",
+                data.len()
+            )));
+        }
+        self.write_u8(data.len() as u8 + 1)?;
+        for unit in data {
+            self.write_u16::<LittleEndian>(unit)?;
+        }
+        Ok(self.write_u16::<LittleEndian>(0)?)
+    }
+}
+
+impl<W: WriteBytesExt + ?Sized> Write for W {}
+
+/// A dataset that can be encoded in the PTP wire format
+pub trait PtpEncode {
+    fn encode_to<W: Write>(&self, w: &mut W) -> Result<(), Error>;
+
+    fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let mut out = vec![];
+        self.encode_to(&mut out)?;
+        Ok(out)
+    }
+}
+
+macro_rules! impl_encode {
+    ($($ty:ty => $write:ident),*) => {
+        $(impl PtpEncode for $ty {
+            fn encode_to<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+                w.$write(*self)
+            }
+        })*
+    };
+}
+
+impl_encode!(u8 => write_ptp_u8, i8 => write_ptp_i8, u16 => write_ptp_u16, i16 => write_ptp_i16, u32 => write_ptp_u32, i32 => write_ptp_i32, u64 => write_ptp_u64, i64 => write_ptp_i64, u128 => write_ptp_u128, i128 => write_ptp_i128);
+
+impl PtpEncode for String {
+    fn encode_to<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_ptp_str(self)
+    }
+}
+
+impl<T: PtpEncode> PtpEncode for Vec<T> {
+    fn encode_to<W: Write>(&self, w: &mut W) -> Result<(), Error> {
+        w.write_ptp_vec(self, |w, item| item.encode_to(w))
+    }
+}
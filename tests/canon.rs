@@ -0,0 +1,108 @@
+use ptp::vendor::canon::{CanonEvent, CanonEventCode, CanonObjectInfo, CanonPropCode};
+use ptp::Error;
+
+fn record(code: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    out.extend_from_slice(&(8 + payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(&code.to_le_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+fn words(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+const TERMINATOR: [u8; 8] = [8, 0, 0, 0, 0, 0, 0, 0];
+
+fn object_added() -> Vec<u8> {
+    let mut payload = words(&[0x9000_0001, 0x0001_0001]);
+    payload.extend_from_slice(&0x3801u16.to_le_bytes());
+    payload.extend_from_slice(&[0; 10]);
+    payload.extend_from_slice(&words(&[6_291_456, 0x9000_0000, 0]));
+    payload.extend_from_slice(b"IMG_0001.JPG\0\0\0\0");
+    record(u32::from(CanonEventCode::ObjectAddedEx), &payload)
+}
+
+#[test]
+fn decode_event_stream() {
+    let mut buf = vec![];
+    buf.extend(record(
+        u32::from(CanonEventCode::PropValueChanged),
+        &words(&[u32::from(CanonPropCode::ISOSpeed), 0x68]),
+    ));
+    buf.extend(record(
+        u32::from(CanonEventCode::AvailListChanged),
+        &words(&[u32::from(CanonPropCode::ISOSpeed), 3, 3, 0x48, 0x50, 0x58]),
+    ));
+    buf.extend(object_added());
+    buf.extend(record(
+        u32::from(CanonEventCode::ObjectRemoved),
+        &words(&[0x9000_0002]),
+    ));
+    buf.extend(record(0xC1F0, &[1, 2, 3]));
+    buf.extend_from_slice(&TERMINATOR);
+    // anything after the terminator is ignored
+    buf.extend_from_slice(&[0xFF; 12]);
+
+    let events = CanonEvent::decode_all(&buf).unwrap();
+    assert_eq!(
+        events,
+        vec![
+            CanonEvent::PropValueChanged {
+                prop: CanonPropCode::ISOSpeed,
+                value: words(&[0x68]),
+            },
+            CanonEvent::AvailListChanged {
+                prop: CanonPropCode::ISOSpeed,
+                data_type: 3,
+                values: vec![0x48, 0x50, 0x58],
+            },
+            CanonEvent::ObjectAdded(CanonObjectInfo {
+                handle: 0x9000_0001,
+                storage_id: 0x0001_0001,
+                format: 0x3801,
+                size: 6_291_456,
+                parent: 0x9000_0000,
+                filename: "IMG_0001.JPG".to_owned(),
+            }),
+            CanonEvent::ObjectRemoved {
+                handle: 0x9000_0002
+            },
+            CanonEvent::Unknown {
+                code: 0xC1F0,
+                data: vec![1, 2, 3],
+            },
+        ]
+    );
+    assert_eq!(events[0].value_u32(), Some(0x68));
+    assert_eq!(events[1].value_u32(), None);
+}
+
+#[test]
+fn decode_empty_stream() {
+    assert_eq!(CanonEvent::decode_all(&TERMINATOR).unwrap(), vec![]);
+    assert_eq!(CanonEvent::decode_all(&[]).unwrap(), vec![]);
+}
+
+#[test]
+fn truncated_record() {
+    let mut buf = object_added();
+    buf.truncate(buf.len() - 4);
+    match CanonEvent::decode_all(&buf) {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn avail_list_longer_than_record() {
+    let buf = record(
+        u32::from(CanonEventCode::AvailListChanged),
+        &words(&[u32::from(CanonPropCode::ISOSpeed), 3, 1000, 0x48]),
+    );
+    match CanonEvent::decode_all(&buf) {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
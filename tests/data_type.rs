@@ -0,0 +1,177 @@
+use ptp::{CountingReader, DataType, Error, FormData, LimitedReader, PropInfo, PropInfoSony};
+use std::io::{self, Cursor, Read};
+
+fn samples() -> Vec<(u16, DataType)> {
+    vec![
+        (0x0001, DataType::INT8(-1)),
+        (0x0002, DataType::UINT8(1)),
+        (0x0003, DataType::INT16(-2)),
+        (0x0004, DataType::UINT16(2)),
+        (0x0005, DataType::INT32(-3)),
+        (0x0006, DataType::UINT32(3)),
+        (0x0007, DataType::INT64(-4)),
+        (0x0008, DataType::UINT64(4)),
+        (0x0009, DataType::INT128(-5)),
+        (0x000A, DataType::UINT128(5)),
+        (0x4001, DataType::AINT8(vec![-1])),
+        (0x4002, DataType::AUINT8(vec![1, 2])),
+        (0x4003, DataType::AINT16(vec![-2])),
+        (0x4004, DataType::AUINT16(vec![2, 3])),
+        (0x4005, DataType::AINT32(vec![-3])),
+        (0x4006, DataType::AUINT32(vec![3, 4])),
+        (0x4007, DataType::AINT64(vec![-4])),
+        (0x4008, DataType::AUINT64(vec![4, 5])),
+        (0x4009, DataType::AINT128(vec![-5])),
+        (0x400A, DataType::AUINT128(vec![5, 6])),
+        (0xFFFF, DataType::STR("ptp".to_owned())),
+    ]
+}
+
+fn assert_malformed<T: std::fmt::Debug>(res: Result<T, Error>) {
+    match res {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn type_code_matches_read_type() {
+    for (code, value) in samples() {
+        assert_eq!(value.type_code(), code, "{:?}", value);
+        let buf = value.encode().unwrap();
+        let mut cur = Cursor::new(&buf[..]);
+        assert_eq!(DataType::read_type(code, &mut cur).unwrap(), value);
+        assert_eq!(cur.position() as usize, buf.len());
+    }
+}
+
+#[test]
+fn undefined_type() {
+    assert_eq!(DataType::UNDEF.type_code(), 0x0000);
+    assert_malformed(DataType::UNDEF.encode());
+    let mut cur = Cursor::new(&[0u8; 4][..]);
+    assert_malformed(DataType::read_type(0x0000, &mut cur));
+    assert_eq!(
+        DataType::read_type_lenient(0x0000, &mut cur).unwrap(),
+        DataType::UNDEF
+    );
+    assert_eq!(cur.position(), 0);
+}
+
+#[test]
+fn unknown_type_codes() {
+    for &code in &[0x000B, 0x0010, 0x4000, 0x400B, 0x8000, 0xD001, 0xFFFE] {
+        let mut cur = Cursor::new(&[0u8; 16][..]);
+        assert_malformed(DataType::read_type(code, &mut cur));
+        assert_eq!(cur.position(), 0);
+    }
+}
+
+#[test]
+fn unknown_type_in_prop_info() {
+    // BatteryLevel claiming a vendor datatype, followed by what would be its values and form
+    let mut buf = vec![0x01, 0x50, 0x01, 0xC0, 0x00];
+    buf.extend_from_slice(&[0x64, 0x00, 0x32, 0x00, 0x00]);
+    assert_malformed(PropInfo::decode(&mut Cursor::new(&buf[..])));
+
+    // a range form is read with the property's datatype too
+    assert_malformed(FormData::read_form(
+        0x4011,
+        &mut Cursor::new(&[0x01, 0, 0, 0, 0][..]),
+    ));
+}
+
+#[test]
+fn huge_enumeration_over_short_payload() {
+    // UINT32 property with an enumeration form claiming 0xFFFF values, but only two present
+    let mut buf = vec![0x01, 0x50, 0x06, 0x00, 0x00];
+    buf.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+    buf.extend_from_slice(&[0x02, 0xFF, 0xFF]);
+    buf.extend_from_slice(&[1, 0, 0, 0, 2, 0, 0, 0]);
+    assert_malformed(PropInfo::decode(&mut Cursor::new(&buf[..])));
+
+    // read from a stream, decoding stops at the end of the payload
+    let stream = CountingReader::new(Cursor::new(buf.clone()).chain(io::repeat(0xAB)));
+    let mut payload = LimitedReader::new(stream, buf.len() as u64);
+    assert_malformed(PropInfo::decode(&mut payload));
+    assert_eq!(payload.into_inner().count(), buf.len() as u64);
+}
+
+#[test]
+fn enumeration_within_payload() {
+    let buf = [0x02, 0x02, 0x00, 0x01, 0x00, 0x02, 0x00];
+    let form = FormData::read_form(0x0004, &mut Cursor::new(&buf[..])).unwrap();
+    assert_eq!(
+        form,
+        FormData::Enumeration {
+            array: vec![DataType::UINT16(1), DataType::UINT16(2)]
+        }
+    );
+}
+
+#[test]
+fn lenient_unknown_types() {
+    let mut cur = Cursor::new(&[1, 2, 3, 4][..]);
+    assert_eq!(
+        DataType::read_type_lenient(0xD001, &mut cur).unwrap(),
+        DataType::UNDEF
+    );
+    assert_eq!(cur.position(), 0);
+    assert_eq!(
+        DataType::read_type_lenient(0x0004, &mut cur).unwrap(),
+        DataType::UINT16(0x0201)
+    );
+
+    // the BatteryLevel of `unknown_type_in_prop_info`
+    let mut buf = vec![0x01, 0x50, 0x01, 0xC0, 0x00];
+    buf.extend_from_slice(&[0x64, 0x00, 0x32, 0x00, 0x00]);
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!(
+        PropInfo::decode_lenient(&mut cur).unwrap(),
+        PropInfo {
+            property_code: 0x5001,
+            data_type: 0xC001,
+            get_set: 0,
+            factory_default: DataType::UNDEF,
+            current: DataType::UNDEF,
+            form: FormData::None,
+        }
+    );
+    assert_eq!(cur.position(), 5);
+}
+
+#[test]
+fn lenient_known_types() {
+    let info = PropInfo {
+        property_code: 0x5001,
+        data_type: 0x0002,
+        get_set: 0,
+        factory_default: DataType::UINT8(100),
+        current: DataType::UINT8(50),
+        form: FormData::Enumeration {
+            array: vec![DataType::UINT8(50), DataType::UINT8(100)],
+        },
+    };
+    let buf = info.encode().unwrap();
+    let mut cur = Cursor::new(&buf[..]);
+    assert_eq!(PropInfo::decode_lenient(&mut cur).unwrap(), info);
+    assert_eq!(cur.position() as usize, buf.len());
+
+    let sony = PropInfoSony {
+        property_code: 0xD20D,
+        data_type: 0x0004,
+        get_set: 1,
+        is_enable: 1,
+        factory_default: DataType::UINT16(0),
+        current: DataType::UINT16(2),
+        form: FormData::None,
+    };
+    let buf = sony.encode().unwrap();
+    assert_eq!(
+        PropInfoSony::decode_lenient(&mut Cursor::new(&buf[..])).unwrap(),
+        sony
+    );
+
+    // truncated headers are still errors
+    assert_malformed(PropInfo::decode_lenient(&mut Cursor::new(&buf[..3])));
+}
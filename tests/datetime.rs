@@ -0,0 +1,375 @@
+use ptp::{Error, PtpDateTime};
+use std::convert::TryFrom;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[allow(clippy::too_many_arguments)]
+fn dt(
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+    tenths: Option<u8>,
+    utc_offset: Option<i16>,
+) -> PtpDateTime {
+    PtpDateTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        tenths,
+        utc_offset,
+    }
+}
+
+fn assert_malformed<T: std::fmt::Debug>(res: Result<T, Error>) {
+    match res {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn parse_local_time() {
+    assert_eq!(
+        PtpDateTime::parse("20240229T235959").unwrap(),
+        dt(2024, 2, 29, 23, 59, 59, None, None)
+    );
+    // padding some devices add
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000\0\0 ").unwrap(),
+        dt(2024, 1, 1, 12, 0, 0, None, None)
+    );
+}
+
+#[test]
+fn parse_tenths() {
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000.5").unwrap(),
+        dt(2024, 1, 1, 12, 0, 0, Some(5), None)
+    );
+    // finer precision is dropped
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000.987Z").unwrap(),
+        dt(2024, 1, 1, 12, 0, 0, Some(9), Some(0))
+    );
+}
+
+#[test]
+fn parse_zone_designators() {
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000Z").unwrap().utc_offset,
+        Some(0)
+    );
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000+0130")
+            .unwrap()
+            .utc_offset,
+        Some(90)
+    );
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000-0800")
+            .unwrap()
+            .utc_offset,
+        Some(-480)
+    );
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000.1-0030").unwrap(),
+        dt(2024, 1, 1, 12, 0, 0, Some(1), Some(-30))
+    );
+}
+
+#[test]
+fn parse_invalid() {
+    for s in &[
+        "",
+        "20240101",
+        "20240101 120000",
+        "2024010aT120000",
+        "20240101T1200",
+        "20240101T120000.",
+        "20240101T120000Q",
+        "20240101T120000Z0",
+        "20240101T120000+01",
+        "20240101T120000+0160",
+        "20240101T120000+2400",
+        "20240101T120000-9959",
+        "20240101T120000+9959",
+        "20240101T120000+01a0",
+        "20240001T120000",
+        "20241301T120000",
+        "20240100T120000",
+        "20230229T120000",
+        "21000229T120000",
+        "20240431T120000",
+        "20240101T240000",
+        "20240101T126000",
+        "20240101T120060",
+        "２0240101T120000",
+    ] {
+        assert_malformed(PtpDateTime::parse(s));
+        assert_malformed(s.parse::<PtpDateTime>());
+    }
+    // leap years divisible by 400
+    assert!(PtpDateTime::parse("20000229T000000").is_ok());
+}
+
+#[test]
+fn display() {
+    for s in &[
+        "20240229T235959",
+        "20240101T120000.5",
+        "20240101T120000Z",
+        "19991231T235959.9+0545",
+        "00010101T000000-1200",
+        "20240101T120000-0030",
+    ] {
+        assert_eq!(PtpDateTime::parse(s).unwrap().to_string(), *s);
+    }
+    assert_eq!(
+        dt(5, 1, 2, 3, 4, 5, None, Some(-61)).to_string(),
+        "00050102T030405-0101"
+    );
+}
+
+#[test]
+fn from_system_time() {
+    assert_eq!(
+        PtpDateTime::from_system_time(UNIX_EPOCH).unwrap(),
+        dt(1970, 1, 1, 0, 0, 0, None, Some(0))
+    );
+    // truncated to whole seconds, also before the epoch
+    assert_eq!(
+        PtpDateTime::from_system_time(UNIX_EPOCH - Duration::from_millis(500)).unwrap(),
+        dt(1969, 12, 31, 23, 59, 59, None, Some(0))
+    );
+    assert_eq!(
+        PtpDateTime::try_from(UNIX_EPOCH + Duration::from_secs(951_868_799)).unwrap(),
+        dt(2000, 2, 29, 23, 59, 59, None, Some(0))
+    );
+    assert_eq!(
+        PtpDateTime::from_system_time_with_offset(UNIX_EPOCH, -480).unwrap(),
+        dt(1969, 12, 31, 16, 0, 0, None, Some(-480))
+    );
+    assert_eq!(
+        PtpDateTime::from_system_time_with_offset(
+            UNIX_EPOCH + Duration::from_secs(951_800_000),
+            330
+        )
+        .unwrap(),
+        dt(2000, 2, 29, 10, 23, 20, None, Some(330))
+    );
+}
+
+#[test]
+fn system_time_out_of_range() {
+    // 10000-01-01T00:00:00Z and one second before 0000-01-01T00:00:00Z
+    let after = UNIX_EPOCH + Duration::from_secs(253_402_300_800);
+    let before = UNIX_EPOCH - Duration::from_secs(62_167_219_201);
+    assert_malformed(PtpDateTime::from_system_time(after));
+    assert_malformed(PtpDateTime::from_system_time(before));
+    assert_malformed(PtpDateTime::from_system_time_with_offset(
+        after - Duration::from_secs(60),
+        60,
+    ));
+
+    assert_eq!(
+        PtpDateTime::from_system_time(after - Duration::from_secs(1)).unwrap(),
+        dt(9999, 12, 31, 23, 59, 59, None, Some(0))
+    );
+    assert_eq!(
+        PtpDateTime::from_system_time(before + Duration::from_secs(1)).unwrap(),
+        dt(0, 1, 1, 0, 0, 0, None, Some(0))
+    );
+}
+
+#[test]
+fn to_system_time() {
+    assert_eq!(
+        PtpDateTime::parse("19700101T000000Z")
+            .unwrap()
+            .to_system_time(),
+        UNIX_EPOCH
+    );
+    // no zone designator is taken as UTC
+    assert_eq!(
+        PtpDateTime::parse("19700101T000001.5")
+            .unwrap()
+            .to_system_time(),
+        UNIX_EPOCH + Duration::from_millis(1500)
+    );
+    assert_eq!(
+        SystemTime::from(PtpDateTime::parse("19691231T160000-0800").unwrap()),
+        UNIX_EPOCH
+    );
+    assert_eq!(
+        PtpDateTime::parse("19691231T235959.5Z")
+            .unwrap()
+            .to_system_time(),
+        UNIX_EPOCH - Duration::from_millis(500)
+    );
+}
+
+#[test]
+fn system_time_round_trip() {
+    for &offset in &[0, 60, -30, -480, 840] {
+        for &secs in &[0i64, 951_782_400, 1_709_251_199, -86_401, -62_167_219_200] {
+            let time = if secs >= 0 {
+                UNIX_EPOCH + Duration::from_secs(secs as u64)
+            } else {
+                UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+            };
+            let local = match PtpDateTime::from_system_time_with_offset(time, offset) {
+                Ok(local) => local,
+                // the earliest time has no year in zones behind UTC
+                Err(_) => {
+                    assert!(offset < 0 && secs == -62_167_219_200);
+                    continue;
+                }
+            };
+            assert_eq!(local.utc_offset, Some(offset));
+            assert_eq!(local.to_system_time(), time);
+            assert_eq!(PtpDateTime::parse(&local.to_string()).unwrap(), local);
+        }
+    }
+}
+
+#[test]
+fn offset_out_of_range() {
+    assert_eq!(
+        PtpDateTime::parse("20240101T120000+2359")
+            .unwrap()
+            .utc_offset,
+        Some(1439)
+    );
+    for &offset in &[1440, -1440, i16::MAX, i16::MIN] {
+        assert_malformed(PtpDateTime::from_system_time_with_offset(
+            UNIX_EPOCH, offset,
+        ));
+    }
+    assert!(PtpDateTime::from_system_time_with_offset(UNIX_EPOCH, -1439).is_ok());
+}
+
+#[test]
+fn now() {
+    let now = PtpDateTime::now().unwrap();
+    assert_eq!(now.utc_offset, Some(0));
+    assert!(now.year >= 2024);
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_conversions {
+    use super::{assert_malformed, dt};
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+    use ptp::PtpDateTime;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn naive() {
+        let naive = NaiveDate::from_ymd_opt(2024, 2, 29)
+            .unwrap()
+            .and_hms_milli_opt(23, 59, 59, 500)
+            .unwrap();
+        let ptp = dt(2024, 2, 29, 23, 59, 59, Some(5), None);
+        assert_eq!(NaiveDateTime::try_from(ptp).unwrap(), naive);
+        // sub-second precision is not carried over
+        assert_eq!(
+            PtpDateTime::try_from(naive).unwrap(),
+            PtpDateTime {
+                tenths: None,
+                ..ptp
+            }
+        );
+    }
+
+    #[test]
+    fn with_offset() {
+        let offset = FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap();
+        let time = offset.with_ymd_and_hms(1999, 12, 31, 19, 0, 0).unwrap();
+        let ptp = PtpDateTime::try_from(time).unwrap();
+        assert_eq!(ptp, dt(1999, 12, 31, 19, 0, 0, None, Some(-330)));
+        assert_eq!(DateTime::<FixedOffset>::try_from(ptp).unwrap(), time);
+
+        let utc = Utc.with_ymd_and_hms(2000, 1, 1, 0, 30, 0).unwrap();
+        assert_eq!(
+            PtpDateTime::try_from(utc).unwrap().to_string(),
+            "20000101T003000Z"
+        );
+    }
+
+    #[test]
+    fn out_of_range() {
+        let naive = NaiveDate::from_ymd_opt(10000, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_malformed(PtpDateTime::try_from(naive));
+        let naive = NaiveDate::from_ymd_opt(-1, 12, 31)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        assert_malformed(PtpDateTime::try_from(naive));
+        assert_malformed(NaiveDateTime::try_from(dt(
+            2023, 2, 29, 0, 0, 0, None, None,
+        )));
+    }
+}
+
+#[cfg(feature = "time")]
+mod time_conversions {
+    use super::{assert_malformed, dt};
+    use ptp::PtpDateTime;
+    use std::convert::TryFrom;
+    use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+    #[test]
+    fn primitive() {
+        let primitive = PrimitiveDateTime::new(
+            Date::from_calendar_date(2024, Month::February, 29).unwrap(),
+            Time::from_hms_milli(23, 59, 59, 500).unwrap(),
+        );
+        let ptp = dt(2024, 2, 29, 23, 59, 59, Some(5), None);
+        assert_eq!(PrimitiveDateTime::try_from(ptp).unwrap(), primitive);
+        assert_eq!(
+            PtpDateTime::try_from(primitive).unwrap(),
+            PtpDateTime {
+                tenths: None,
+                ..ptp
+            }
+        );
+    }
+
+    #[test]
+    fn with_offset() {
+        let time = PrimitiveDateTime::new(
+            Date::from_calendar_date(1999, Month::December, 31).unwrap(),
+            Time::from_hms(19, 0, 0).unwrap(),
+        )
+        .assume_offset(UtcOffset::from_hms(-5, -30, 0).unwrap());
+        let ptp = PtpDateTime::try_from(time).unwrap();
+        assert_eq!(ptp, dt(1999, 12, 31, 19, 0, 0, None, Some(-330)));
+        assert_eq!(OffsetDateTime::try_from(ptp).unwrap(), time);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let primitive = PrimitiveDateTime::new(
+            Date::from_calendar_date(-1, Month::December, 31).unwrap(),
+            Time::MIDNIGHT,
+        );
+        assert_malformed(PtpDateTime::try_from(primitive));
+        assert_malformed(PrimitiveDateTime::try_from(dt(
+            2023, 2, 29, 0, 0, 0, None, None,
+        )));
+        // the time crate allows offsets up to ±25:59:59
+        assert_malformed(PtpDateTime::try_from(
+            primitive
+                .replace_year(2024)
+                .unwrap()
+                .assume_offset(UtcOffset::from_hms(25, 0, 0).unwrap()),
+        ));
+    }
+}
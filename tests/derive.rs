@@ -0,0 +1,167 @@
+use ptp::{Error, PtpDataset, PtpDecode, PtpEncode};
+
+// a fixed four-byte ASCII field, as vendor datasets use
+mod tag {
+    use ptp::{Error, Read, Write};
+
+    pub fn decode<R: Read>(r: &mut R) -> Result<String, Error> {
+        let mut bytes = [0; 4];
+        for b in bytes.iter_mut() {
+            *b = r.read_ptp_u8()?;
+        }
+        Ok(String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .to_owned())
+    }
+
+    pub fn encode<W: Write>(value: &str, w: &mut W) -> Result<(), Error> {
+        let mut bytes = [0; 4];
+        bytes[..value.len()].copy_from_slice(value.as_bytes());
+        for b in bytes.iter() {
+            w.write_ptp_u8(*b)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, PtpDataset)]
+struct Record {
+    id: u32,
+    #[ptp(with = "tag")]
+    tag: String,
+    values: Vec<u16>,
+    #[ptp(skip)]
+    cached: Option<usize>,
+    name: String,
+    #[ptp(optional)]
+    flags: Option<u16>,
+    #[ptp(optional)]
+    extra: Option<u32>,
+}
+
+fn record() -> Record {
+    Record {
+        id: 0x0102_0304,
+        tag: "JPG".to_owned(),
+        values: vec![1, 2],
+        cached: None,
+        name: "a".to_owned(),
+        flags: Some(0xBEEF),
+        extra: Some(7),
+    }
+}
+
+// the encoding of `record()`, and the offset of the first optional field
+fn record_bytes() -> (Vec<u8>, usize) {
+    let mut buf = vec![0x04, 0x03, 0x02, 0x01];
+    buf.extend_from_slice(b"JPG\0");
+    buf.extend_from_slice(&[2, 0, 0, 0, 1, 0, 2, 0]);
+    buf.extend_from_slice(&[2, b'a', 0, 0, 0]);
+    let optional = buf.len();
+    buf.extend_from_slice(&[0xEF, 0xBE]);
+    buf.extend_from_slice(&[7, 0, 0, 0]);
+    (buf, optional)
+}
+
+fn assert_malformed(res: Result<Record, Error>) {
+    match res {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn round_trip() {
+    let (bytes, _) = record_bytes();
+    assert_eq!(record().to_bytes().unwrap(), bytes);
+    assert_eq!(Record::from_bytes(&bytes).unwrap(), record());
+}
+
+#[test]
+fn skipped_field_not_encoded() {
+    let with_cache = Record {
+        cached: Some(42),
+        ..record()
+    };
+    let bytes = with_cache.to_bytes().unwrap();
+    assert_eq!(bytes, record_bytes().0);
+    assert_eq!(Record::from_bytes(&bytes).unwrap().cached, None);
+}
+
+#[test]
+fn missing_trailing_optional_fields() {
+    let (bytes, optional) = record_bytes();
+
+    let decoded = Record::from_bytes(&bytes[..optional + 2]).unwrap();
+    assert_eq!(decoded.flags, Some(0xBEEF));
+    assert_eq!(decoded.extra, None);
+
+    let decoded = Record::from_bytes(&bytes[..optional]).unwrap();
+    assert_eq!(
+        decoded,
+        Record {
+            flags: None,
+            extra: None,
+            ..record()
+        }
+    );
+
+    // absent optional fields are not encoded
+    assert_eq!(decoded.to_bytes().unwrap(), &bytes[..optional]);
+}
+
+#[test]
+fn truncated_optional_field() {
+    let (bytes, optional) = record_bytes();
+    assert_malformed(Record::from_bytes(&bytes[..optional + 1]));
+    assert_malformed(Record::from_bytes(&bytes[..optional + 4]));
+}
+
+#[test]
+fn truncated_required_field() {
+    let (bytes, optional) = record_bytes();
+    assert_malformed(Record::from_bytes(&bytes[..optional - 1]));
+    assert_malformed(Record::from_bytes(&bytes[..6]));
+    assert_malformed(Record::from_bytes(&[]));
+}
+
+// a stream that fails once the data runs out, instead of ending
+struct Failing<'a>(&'a [u8]);
+
+impl std::io::Read for Failing<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.0.is_empty() {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
+        std::io::Read::read(&mut self.0, buf)
+    }
+}
+
+#[test]
+fn optional_field_error_not_absent() {
+    let (bytes, optional) = record_bytes();
+    assert_malformed(Record::decode_from(&mut Failing(&bytes[..optional])));
+    // a stream that ends is still missing its optional fields
+    let decoded =
+        Record::decode_from(&mut std::io::Read::chain(&bytes[..optional], &[][..])).unwrap();
+    assert_eq!(decoded.flags, None);
+    assert_eq!(decoded.extra, None);
+}
+
+#[test]
+fn optional_field_after_absent_one() {
+    let gap = Record {
+        flags: None,
+        ..record()
+    };
+    match gap.to_bytes() {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+    let trailing = Record {
+        extra: None,
+        ..record()
+    };
+    let (bytes, optional) = record_bytes();
+    assert_eq!(trailing.to_bytes().unwrap(), &bytes[..optional + 2]);
+}
@@ -0,0 +1,184 @@
+use ptp::vendor::mtp::MtpEventCode;
+use ptp::{Error, PtpEvent, StandardEventCode, StandardPropCode};
+
+fn event_container(code: u16, tid: u32, params: &[u32]) -> Vec<u8> {
+    let mut buf = vec![];
+    buf.extend_from_slice(&(12 + 4 * params.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&4u16.to_le_bytes());
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&tid.to_le_bytes());
+    for p in params {
+        buf.extend_from_slice(&p.to_le_bytes());
+    }
+    buf
+}
+
+#[test]
+fn standard_codes() {
+    let cases = vec![
+        (
+            StandardEventCode::CancelTransaction,
+            vec![],
+            PtpEvent::CancelTransaction { transaction_id: 9 },
+        ),
+        (
+            StandardEventCode::ObjectAdded,
+            vec![0x10],
+            PtpEvent::ObjectAdded { handle: 0x10 },
+        ),
+        (
+            StandardEventCode::ObjectRemoved,
+            vec![0x10],
+            PtpEvent::ObjectRemoved { handle: 0x10 },
+        ),
+        (
+            StandardEventCode::StoreAdded,
+            vec![0x0001_0001],
+            PtpEvent::StoreAdded {
+                storage_id: 0x0001_0001,
+            },
+        ),
+        (
+            StandardEventCode::StoreRemoved,
+            vec![0x0001_0001],
+            PtpEvent::StoreRemoved {
+                storage_id: 0x0001_0001,
+            },
+        ),
+        (
+            StandardEventCode::DevicePropChanged,
+            vec![u32::from(StandardPropCode::BatteryLevel)],
+            PtpEvent::DevicePropChanged {
+                prop: StandardPropCode::BatteryLevel,
+            },
+        ),
+        (
+            StandardEventCode::ObjectInfoChanged,
+            vec![0x10],
+            PtpEvent::ObjectInfoChanged { handle: 0x10 },
+        ),
+        (
+            StandardEventCode::DeviceInfoChanged,
+            vec![],
+            PtpEvent::DeviceInfoChanged,
+        ),
+        (
+            StandardEventCode::RequestObjectTransfer,
+            vec![0x10],
+            PtpEvent::RequestObjectTransfer { handle: 0x10 },
+        ),
+        (
+            StandardEventCode::StoreFull,
+            vec![0x0001_0001],
+            PtpEvent::StoreFull {
+                storage_id: 0x0001_0001,
+            },
+        ),
+        (
+            StandardEventCode::DeviceReset,
+            vec![],
+            PtpEvent::DeviceReset,
+        ),
+        (
+            StandardEventCode::StorageInfoChanged,
+            vec![0x0001_0001],
+            PtpEvent::StorageInfoChanged {
+                storage_id: 0x0001_0001,
+            },
+        ),
+        (
+            StandardEventCode::CaptureComplete,
+            vec![],
+            PtpEvent::CaptureComplete { transaction_id: 9 },
+        ),
+        (
+            StandardEventCode::UnreportedStatus,
+            vec![],
+            PtpEvent::UnreportedStatus,
+        ),
+    ];
+    for (code, params, expected) in cases {
+        let event = PtpEvent::from_parts(code, 9, params).unwrap();
+        assert_eq!(event, expected);
+        assert_eq!(event.code(), code);
+    }
+}
+
+#[test]
+fn mtp_codes() {
+    let event =
+        PtpEvent::from_parts(MtpEventCode::ObjectPropChanged, 0, vec![0x10, 0xDC07]).unwrap();
+    assert_eq!(
+        event,
+        PtpEvent::ObjectPropChanged {
+            handle: 0x10,
+            prop: 0xDC07,
+        }
+    );
+    assert_eq!(event.code(), MtpEventCode::ObjectPropChanged);
+
+    let event =
+        PtpEvent::from_parts(MtpEventCode::ObjectPropDescChanged, 0, vec![0xDC07, 0x3801]).unwrap();
+    assert_eq!(
+        event,
+        PtpEvent::ObjectPropDescChanged {
+            prop: 0xDC07,
+            format: 0x3801,
+        }
+    );
+    assert_eq!(event.code(), MtpEventCode::ObjectPropDescChanged);
+}
+
+#[test]
+fn vendor_codes_kept_as_other() {
+    let event = PtpEvent::from_parts(0xC101, 3, vec![1, 2, 3, 4]).unwrap();
+    assert_eq!(
+        event,
+        PtpEvent::Other {
+            code: 0xC101,
+            transaction_id: 3,
+            params: vec![1, 2, 3, 4],
+        }
+    );
+    assert_eq!(event.code(), 0xC101);
+}
+
+#[test]
+fn missing_parameters() {
+    for &(code, params) in &[
+        (StandardEventCode::ObjectAdded, &[][..]),
+        (MtpEventCode::ObjectPropChanged, &[0x10][..]),
+        (MtpEventCode::ObjectPropDescChanged, &[0xDC07][..]),
+    ] {
+        match PtpEvent::from_parts(code, 0, params.to_vec()) {
+            Err(Error::Malformed(_)) => {}
+            other => panic!("expected Malformed, got {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn decode_container() {
+    let buf = event_container(StandardEventCode::ObjectAdded, 5, &[0x10]);
+    assert_eq!(
+        PtpEvent::decode(&buf).unwrap(),
+        PtpEvent::ObjectAdded { handle: 0x10 }
+    );
+
+    // a vendor event with more parameters than standard events carry
+    let buf = event_container(0xC101, 5, &[1, 2, 3, 4, 5]);
+    assert_eq!(
+        PtpEvent::decode(&buf).unwrap(),
+        PtpEvent::Other {
+            code: 0xC101,
+            transaction_id: 5,
+            params: vec![1, 2, 3, 4, 5],
+        }
+    );
+
+    // cut short, e.g. by a read smaller than the container
+    match PtpEvent::decode(&buf[..24]) {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
@@ -0,0 +1,150 @@
+use ptp::vendor::nikon::{NikonEvent, NikonEventCode, NikonLiveViewArea, NikonLiveViewFrame};
+use ptp::{Error, StandardEventCode};
+
+fn event_list(events: &[(u16, u32)]) -> Vec<u8> {
+    let mut out = (events.len() as u16).to_le_bytes().to_vec();
+    for &(code, param) in events {
+        out.extend_from_slice(&code.to_le_bytes());
+        out.extend_from_slice(&param.to_le_bytes());
+    }
+    out
+}
+
+#[test]
+fn decode_event_list() {
+    let buf = event_list(&[
+        (NikonEventCode::ObjectAddedInSdram, 0xFFFF_0001),
+        (StandardEventCode::DevicePropChanged, 0x5007),
+        (StandardEventCode::CaptureComplete, 0),
+    ]);
+    assert_eq!(
+        NikonEvent::decode_all(&buf).unwrap(),
+        vec![
+            NikonEvent {
+                code: NikonEventCode::ObjectAddedInSdram,
+                param: 0xFFFF_0001,
+            },
+            NikonEvent {
+                code: StandardEventCode::DevicePropChanged,
+                param: 0x5007,
+            },
+            NikonEvent {
+                code: StandardEventCode::CaptureComplete,
+                param: 0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn decode_empty_event_list() {
+    assert_eq!(NikonEvent::decode_all(&[0, 0]).unwrap(), vec![]);
+    assert!(NikonEvent::decode_all(&[]).is_err());
+}
+
+#[test]
+fn event_list_count_past_end() {
+    let mut buf = event_list(&[(StandardEventCode::ObjectAdded, 1)]);
+    buf[0] = 2;
+    match NikonEvent::decode_all(&buf) {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+#[test]
+fn event_list_trailing_bytes() {
+    let mut buf = event_list(&[(StandardEventCode::ObjectAdded, 1)]);
+    buf.extend_from_slice(&[0, 0]);
+    assert!(NikonEvent::decode_all(&buf).is_err());
+}
+
+const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0xFF, 0xD9];
+
+// a live view payload with a header of `header_len` bytes, fields big-endian
+fn live_view(header_len: usize) -> Vec<u8> {
+    let mut buf = vec![0; header_len];
+    let geometry: [u16; 12] = [
+        640, 424, 6016, 4016, 6016, 4016, 3008, 2008, 300, 200, 1500, 1000,
+    ];
+    for (i, v) in geometry.iter().enumerate() {
+        buf[i * 2..i * 2 + 2].copy_from_slice(&v.to_be_bytes());
+    }
+    if header_len > 28 {
+        buf[27] = 1;
+        buf[28] = 1;
+    }
+    if header_len > 46 {
+        buf[42..46].copy_from_slice(&(-150i32).to_be_bytes());
+    }
+    buf.extend_from_slice(JPEG);
+    buf
+}
+
+fn assert_geometry(frame: &NikonLiveViewFrame) {
+    assert_eq!(frame.jpeg, JPEG);
+    assert_eq!((frame.jpeg_width, frame.jpeg_height), (640, 424));
+    assert_eq!((frame.whole_width, frame.whole_height), (6016, 4016));
+    assert_eq!(
+        frame.display_area,
+        NikonLiveViewArea {
+            center_x: 3008,
+            center_y: 2008,
+            width: 6016,
+            height: 4016,
+        }
+    );
+    assert_eq!(
+        frame.af_area,
+        NikonLiveViewArea {
+            center_x: 1500,
+            center_y: 1000,
+            width: 300,
+            height: 200,
+        }
+    );
+}
+
+#[test]
+fn live_view_header_v1() {
+    let frame = NikonLiveViewFrame::decode("D300", &live_view(64)).unwrap();
+    assert_geometry(&frame);
+    assert_eq!(frame.rotation, None);
+    assert_eq!(frame.focus_driving, None);
+    assert_eq!(frame.level, None);
+}
+
+#[test]
+fn live_view_header_v2() {
+    let frame = NikonLiveViewFrame::decode("Nikon D90", &live_view(128)).unwrap();
+    assert_geometry(&frame);
+    assert_eq!(frame.rotation, Some(1));
+    assert_eq!(frame.focus_driving, Some(true));
+    assert_eq!(frame.level, None);
+}
+
+#[test]
+fn live_view_header_v3() {
+    let frame = NikonLiveViewFrame::decode("D7000", &live_view(384)).unwrap();
+    assert_geometry(&frame);
+    assert_eq!(frame.rotation, Some(1));
+    assert_eq!(frame.focus_driving, Some(true));
+    assert_eq!(frame.level, Some(-150));
+}
+
+#[test]
+fn live_view_header_size_mismatch() {
+    // a V2 body unknown to the layout table, decoded with the larger V3 header
+    let mut buf = live_view(128);
+    buf.resize(400, 0);
+    let frame = NikonLiveViewFrame::decode("D5100", &buf).unwrap();
+    assert!(frame.jpeg.starts_with(JPEG));
+}
+
+#[test]
+fn live_view_truncated_header() {
+    match NikonLiveViewFrame::decode("D7000", &live_view(128)[..100]) {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
@@ -0,0 +1,488 @@
+use ptp::vendor::fuji::FujiPropCode;
+use ptp::{
+    Camera, DataType, DeviceInfo, Error, ObjectInfo, PtpEvent, PtpIpHandshake, PtpIpOptions,
+    PtpIpResponder, StandardCommandCode, StandardEventCode, StandardPropCode, StandardResponseCode,
+};
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Option<Duration> = Some(Duration::from_secs(5));
+
+fn words(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn utf16z(s: &str) -> Vec<u8> {
+    s.encode_utf16()
+        .chain(Some(0))
+        .flat_map(|u| u.to_le_bytes())
+        .collect()
+}
+
+fn device_info() -> DeviceInfo {
+    DeviceInfo {
+        Version: 100,
+        VendorExID: 0x0E,
+        VendorExVersion: 100,
+        VendorExtensionDesc: "fujifilm.co.jp: 1.0;".to_owned(),
+        FunctionalMode: 0,
+        OperationsSupported: vec![0x1001, 0x1002],
+        EventsSupported: vec![0x4002],
+        DevicePropertiesSupported: vec![0x5001],
+        CaptureFormats: vec![],
+        ImageFormats: vec![0x3801],
+        Manufacturer: "Test".to_owned(),
+        Model: "Scripted".to_owned(),
+        DeviceVersion: "1.0".to_owned(),
+        SerialNumber: "0001".to_owned(),
+    }
+}
+
+// the responder's side of the standard protocol
+fn read_packet(s: &mut TcpStream) -> (u32, Vec<u8>) {
+    let mut header = [0; 8];
+    s.read_exact(&mut header).unwrap();
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let kind = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    let mut payload = vec![0; len - 8];
+    s.read_exact(&mut payload).unwrap();
+    (kind, payload)
+}
+
+fn write_packet(s: &mut TcpStream, kind: u32, payload: &[u8]) {
+    let mut buf = words(&[8 + payload.len() as u32, kind]);
+    buf.extend_from_slice(payload);
+    s.write_all(&buf).unwrap();
+}
+
+// an OperationRequest: data phase, code, transaction ID, parameters
+fn operation(data_phase: u32, code: u16, tid: u32, params: &[u32]) -> Vec<u8> {
+    let mut buf = data_phase.to_le_bytes().to_vec();
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&tid.to_le_bytes());
+    buf.extend(words(params));
+    buf
+}
+
+fn respond_ok(s: &mut TcpStream, tid: u32) {
+    let mut payload = StandardResponseCode::Ok.to_le_bytes().to_vec();
+    payload.extend_from_slice(&tid.to_le_bytes());
+    write_packet(s, 7, &payload);
+}
+
+fn standard_handshake(listener: &TcpListener) -> (TcpStream, TcpStream) {
+    let (mut cmd, _) = listener.accept().unwrap();
+    let (kind, payload) = read_packet(&mut cmd);
+    assert_eq!(kind, 1);
+    let mut expected = b"0123456789abcdef".to_vec();
+    expected.extend(utf16z("test"));
+    expected.extend(words(&[0x0001_0000]));
+    assert_eq!(payload, expected);
+
+    let mut ack = words(&[7]);
+    ack.extend_from_slice(b"fedcba9876543210");
+    ack.extend(utf16z("Scripted"));
+    ack.extend(words(&[0x0001_0000]));
+    write_packet(&mut cmd, 2, &ack);
+
+    let (mut event, _) = listener.accept().unwrap();
+    assert_eq!(read_packet(&mut event), (3, words(&[7])));
+    write_packet(&mut event, 4, &[]);
+    (cmd, event)
+}
+
+fn options(handshake: PtpIpHandshake) -> PtpIpOptions {
+    PtpIpOptions {
+        guid: *b"0123456789abcdef",
+        name: "test".to_owned(),
+        handshake,
+        ..PtpIpOptions::default()
+    }
+}
+
+#[test]
+fn standard_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, mut event) = standard_handshake(&listener);
+
+        assert_eq!(
+            read_packet(&mut cmd),
+            (
+                6,
+                operation(1, StandardCommandCode::OpenSession, 0, &[1, 0, 0])
+            )
+        );
+        respond_ok(&mut cmd, 0);
+
+        // data-in phase split over Data and EndData packets
+        assert_eq!(
+            read_packet(&mut cmd),
+            (
+                6,
+                operation(1, StandardCommandCode::GetDeviceInfo, 1, &[0, 0, 0])
+            )
+        );
+        let data = device_info().encode().unwrap();
+        let mut start = words(&[1]);
+        start.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        write_packet(&mut cmd, 9, &start);
+        let (first, rest) = data.split_at(10);
+        write_packet(&mut cmd, 10, &[&words(&[1])[..], first].concat());
+        write_packet(&mut cmd, 12, &[&words(&[1])[..], rest].concat());
+        respond_ok(&mut cmd, 1);
+
+        // data-out phase
+        assert_eq!(
+            read_packet(&mut cmd),
+            (
+                6,
+                operation(
+                    2,
+                    StandardCommandCode::SetDevicePropValue,
+                    2,
+                    &[u32::from(StandardPropCode::BatteryLevel)]
+                )
+            )
+        );
+        let mut start = words(&[2]);
+        start.extend_from_slice(&2u64.to_le_bytes());
+        assert_eq!(read_packet(&mut cmd), (9, start));
+        assert_eq!(
+            read_packet(&mut cmd),
+            (12, [2, 0, 0, 0, 0x34, 0x12].to_vec())
+        );
+        respond_ok(&mut cmd, 2);
+
+        let mut ev = StandardEventCode::ObjectAdded.to_le_bytes().to_vec();
+        ev.extend(words(&[0xFFFF_FFFF, 0x42]));
+        write_packet(&mut event, 8, &ev);
+
+        assert_eq!(
+            read_packet(&mut cmd),
+            (6, operation(1, StandardCommandCode::CloseSession, 3, &[]))
+        );
+        respond_ok(&mut cmd, 3);
+    });
+
+    let mut camera = Camera::connect_ip(addr, &options(PtpIpHandshake::Standard), TIMEOUT).unwrap();
+    assert_eq!(
+        camera.ptpip_responder(),
+        Some(&PtpIpResponder {
+            connection_number: 7,
+            guid: *b"fedcba9876543210",
+            name: "Scripted".to_owned(),
+            protocol_version: 0x0001_0000,
+        })
+    );
+    camera.open_session(TIMEOUT).unwrap();
+    assert_eq!(camera.get_device_info(TIMEOUT).unwrap(), device_info());
+    camera
+        .set_device_prop_value(
+            StandardPropCode::BatteryLevel,
+            &DataType::UINT16(0x1234),
+            TIMEOUT,
+        )
+        .unwrap();
+    assert_eq!(
+        camera.event(TIMEOUT).unwrap(),
+        Some(PtpEvent::ObjectAdded { handle: 0x42 })
+    );
+    assert!(matches!(camera.reset(), Err(Error::Unsupported(_))));
+    camera.disconnect(TIMEOUT).unwrap();
+    server.join().unwrap();
+}
+
+#[test]
+fn standard_init_fail() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, _) = listener.accept().unwrap();
+        assert_eq!(read_packet(&mut cmd).0, 1);
+        write_packet(&mut cmd, 5, &words(&[0x2019]));
+    });
+
+    match Camera::connect_ip(addr, &options(PtpIpHandshake::Standard), TIMEOUT) {
+        Err(Error::Io(ref e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {}
+        Err(e) => panic!("expected a refused connection, got {:?}", e),
+        Ok(_) => panic!("expected a refused connection"),
+    }
+    server.join().unwrap();
+}
+
+// the responder's side of Fuji's USB-style containers
+fn read_container(s: &mut TcpStream) -> (u16, u16, u32, Vec<u8>) {
+    let mut header = [0; 12];
+    s.read_exact(&mut header).unwrap();
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let mut payload = vec![0; len - 12];
+    s.read_exact(&mut payload).unwrap();
+    (
+        u16::from_le_bytes([header[4], header[5]]),
+        u16::from_le_bytes([header[6], header[7]]),
+        u32::from_le_bytes([header[8], header[9], header[10], header[11]]),
+        payload,
+    )
+}
+
+fn write_container(s: &mut TcpStream, kind: u16, code: u16, tid: u32, payload: &[u8]) {
+    let mut buf = words(&[12 + payload.len() as u32]);
+    buf.extend_from_slice(&kind.to_le_bytes());
+    buf.extend_from_slice(&code.to_le_bytes());
+    buf.extend_from_slice(&tid.to_le_bytes());
+    buf.extend_from_slice(payload);
+    s.write_all(&buf).unwrap();
+}
+
+#[test]
+fn fuji_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let event_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let event_port = event_listener.local_addr().unwrap().port();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, _) = listener.accept().unwrap();
+        let (kind, payload) = read_packet(&mut cmd);
+        assert_eq!(kind, 1);
+        assert_eq!(payload.len(), 0x52 - 8);
+        assert_eq!(&payload[..4], &0x8F53_E4F2u32.to_le_bytes());
+        assert_eq!(&payload[4..20], b"0123456789abcdef");
+        let mut name = utf16z("test");
+        name.resize(54, 0);
+        assert_eq!(&payload[20..], &name[..]);
+        write_packet(&mut cmd, 2, &words(&[0]));
+
+        assert_eq!(
+            read_container(&mut cmd),
+            (1, StandardCommandCode::OpenSession, 0, words(&[1, 0, 0]))
+        );
+        write_container(&mut cmd, 3, StandardResponseCode::Ok, 0, &[]);
+
+        assert_eq!(
+            read_container(&mut cmd),
+            (
+                1,
+                StandardCommandCode::GetDevicePropValue,
+                1,
+                words(&[u32::from(FujiPropCode::FilmSimulation)])
+            )
+        );
+        write_container(
+            &mut cmd,
+            2,
+            StandardCommandCode::GetDevicePropValue,
+            1,
+            &[0x02, 0x00],
+        );
+        write_container(&mut cmd, 3, StandardResponseCode::Ok, 1, &[]);
+
+        // events come on a second connection, opened without a handshake
+        let (mut event, _) = event_listener.accept().unwrap();
+        write_container(
+            &mut event,
+            4,
+            StandardEventCode::DevicePropChanged,
+            0,
+            &words(&[u32::from(FujiPropCode::FilmSimulation)]),
+        );
+    });
+
+    let options = PtpIpOptions {
+        event_port: Some(event_port),
+        ..options(PtpIpHandshake::Fuji)
+    };
+    let mut camera = Camera::connect_ip(addr, &options, TIMEOUT).unwrap();
+    assert_eq!(camera.ptpip_responder(), None);
+    camera.open_session(TIMEOUT).unwrap();
+    assert_eq!(camera.fuji_film_simulation(TIMEOUT).unwrap(), 2);
+    assert_eq!(
+        camera.event(TIMEOUT).unwrap(),
+        Some(PtpEvent::DevicePropChanged {
+            prop: FujiPropCode::FilmSimulation
+        })
+    );
+    server.join().unwrap();
+}
+
+#[test]
+fn fuji_event_port_past_last_port() {
+    // the event port would follow the command port, and nothing follows 65535
+    match Camera::connect_ip("127.0.0.1:65535", &options(PtpIpHandshake::Fuji), TIMEOUT) {
+        Err(Error::Io(ref e)) if e.kind() == std::io::ErrorKind::InvalidInput => {}
+        Err(e) => panic!("expected InvalidInput, got {:?}", e),
+        Ok(_) => panic!("connected without an event port"),
+    }
+}
+
+fn object_info(name: &str) -> ObjectInfo {
+    ObjectInfo {
+        StorageID: 0x0001_0001,
+        ObjectFormat: 0x3801,
+        ProtectionStatus: 0,
+        ObjectCompressedSize: 1024,
+        ThumbFormat: 0,
+        ThumbCompressedSize: 0,
+        ThumbPixWidth: 0,
+        ThumbPixHeight: 0,
+        ImagePixWidth: 0,
+        ImagePixHeight: 0,
+        ImageBitDepth: 0,
+        ParentObject: 0,
+        AssociationType: 0,
+        AssociationDesc: 0,
+        SequenceNumber: 0,
+        Filename: name.to_owned(),
+        CaptureDate: String::new(),
+        ModificationDate: String::new(),
+        Keywords: String::new(),
+    }
+}
+
+// an OperationRequest as its code, transaction ID and parameters
+fn read_operation(s: &mut TcpStream) -> (u16, u32, Vec<u32>) {
+    let (kind, payload) = read_packet(s);
+    assert_eq!(kind, 6);
+    let code = u16::from_le_bytes([payload[4], payload[5]]);
+    let tid = u32::from_le_bytes([payload[6], payload[7], payload[8], payload[9]]);
+    let params = payload[10..]
+        .chunks(4)
+        .map(|w| u32::from_le_bytes([w[0], w[1], w[2], w[3]]))
+        .collect();
+    (code, tid, params)
+}
+
+// a data-in phase in a single EndData packet
+fn send_data(s: &mut TcpStream, tid: u32, data: &[u8]) {
+    let mut start = words(&[tid]);
+    start.extend_from_slice(&(data.len() as u64).to_le_bytes());
+    write_packet(s, 9, &start);
+    write_packet(s, 12, &[&words(&[tid])[..], data].concat());
+}
+
+fn send_event(s: &mut TcpStream, code: u16, tid: u32, params: &[u32]) {
+    let mut ev = code.to_le_bytes().to_vec();
+    ev.extend(words(&[tid]));
+    ev.extend(words(params));
+    write_packet(s, 8, &ev);
+}
+
+// answer GetObjectInfo for each of `handles`, then CloseSession
+fn serve_object_infos(cmd: &mut TcpStream, handles: &[u32]) {
+    for &handle in handles {
+        let (code, tid, params) = read_operation(cmd);
+        assert_eq!(
+            (code, params),
+            (StandardCommandCode::GetObjectInfo, vec![handle])
+        );
+        send_data(
+            cmd,
+            tid,
+            &object_info(&format!("{:x}", handle)).encode().unwrap(),
+        );
+        respond_ok(cmd, tid);
+    }
+    let (code, tid, _) = read_operation(cmd);
+    assert_eq!(code, StandardCommandCode::CloseSession);
+    respond_ok(cmd, tid);
+}
+
+#[test]
+fn capture_without_capture_complete() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, mut event) = standard_handshake(&listener);
+        let (code, tid, _) = read_operation(&mut cmd);
+        assert_eq!(code, StandardCommandCode::InitiateCapture);
+        respond_ok(&mut cmd, tid);
+        // a RAW+JPEG pair, and no CaptureComplete
+        send_event(&mut event, StandardEventCode::ObjectAdded, tid, &[0x41]);
+        send_event(&mut event, StandardEventCode::ObjectAdded, tid, &[0x42]);
+        serve_object_infos(&mut cmd, &[0x41, 0x42]);
+    });
+
+    let mut camera = Camera::connect_ip(addr, &options(PtpIpHandshake::Standard), TIMEOUT).unwrap();
+    // without a timeout, the capture still ends once the device goes quiet
+    let start = Instant::now();
+    let objects = camera.capture(0, 0, None).unwrap();
+    assert!(start.elapsed() >= Duration::from_secs(2));
+    assert!(start.elapsed() < Duration::from_secs(4));
+    assert_eq!(
+        objects,
+        vec![(0x41, object_info("41")), (0x42, object_info("42"))]
+    );
+    camera.disconnect(TIMEOUT).unwrap();
+    server.join().unwrap();
+}
+
+#[test]
+fn capture_store_full() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, mut event) = standard_handshake(&listener);
+        let (code, tid, _) = read_operation(&mut cmd);
+        assert_eq!(code, StandardCommandCode::InitiateCapture);
+        respond_ok(&mut cmd, tid);
+        send_event(
+            &mut event,
+            StandardEventCode::StoreFull,
+            tid,
+            &[0x0001_0001],
+        );
+        serve_object_infos(&mut cmd, &[]);
+    });
+
+    let mut camera = Camera::connect_ip(addr, &options(PtpIpHandshake::Standard), TIMEOUT).unwrap();
+    match camera.capture(0, 0, TIMEOUT) {
+        Err(Error::StoreFull(0x0001_0001)) => {}
+        other => panic!("expected StoreFull, got {:?}", other),
+    }
+    camera.disconnect(TIMEOUT).unwrap();
+    server.join().unwrap();
+}
+
+#[test]
+fn open_capture_without_capture_complete() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = thread::spawn(move || {
+        let (mut cmd, mut event) = standard_handshake(&listener);
+        let (code, tid, _) = read_operation(&mut cmd);
+        assert_eq!(code, StandardCommandCode::InitiateOpenCapture);
+        respond_ok(&mut cmd, tid);
+        send_event(&mut event, StandardEventCode::ObjectAdded, tid, &[0x51]);
+
+        let (code, terminate_tid, params) = read_operation(&mut cmd);
+        assert_eq!(
+            (code, params),
+            (StandardCommandCode::TerminateOpenCapture, vec![tid])
+        );
+        respond_ok(&mut cmd, terminate_tid);
+        // the last object arrives after termination, and no CaptureComplete
+        send_event(&mut event, StandardEventCode::ObjectAdded, tid, &[0x52]);
+        serve_object_infos(&mut cmd, &[0x51, 0x52]);
+    });
+
+    let mut camera = Camera::connect_ip(addr, &options(PtpIpHandshake::Standard), TIMEOUT).unwrap();
+    let start = Instant::now();
+    let capture = camera.open_capture(0, 0, None).unwrap();
+    let objects = capture.stop_after(Duration::from_millis(200)).unwrap();
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        objects,
+        vec![(0x51, object_info("51")), (0x52, object_info("52"))]
+    );
+    camera.disconnect(TIMEOUT).unwrap();
+    server.join().unwrap();
+}
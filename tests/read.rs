@@ -0,0 +1,112 @@
+use ptp::{CountingReader, Error, LimitedReader, PtpDecode, PtpEncode, Read, ReadEnd, StorageInfo};
+use std::io::{self, Cursor, Read as _};
+
+fn storage_info() -> StorageInfo {
+    StorageInfo {
+        StorageType: 4,
+        FilesystemType: 3,
+        AccessCapability: 0,
+        MaxCapacity: 64 << 30,
+        FreeSpaceInBytes: 12 << 30,
+        FreeSpaceInImages: 1500,
+        StorageDescription: "SD1".to_owned(),
+        VolumeLabel: "EOS_DIGITAL".to_owned(),
+    }
+}
+
+#[test]
+fn decode_from_stream() {
+    let bytes = storage_info().to_bytes().unwrap();
+    // a plain slice reader has no position to check, but decodes all the same
+    let mut stream = CountingReader::new(&bytes[..]);
+    assert_eq!(
+        StorageInfo::decode_from(&mut stream).unwrap(),
+        storage_info()
+    );
+    assert_eq!(stream.count(), bytes.len() as u64);
+}
+
+#[test]
+fn limited_reader_stops_at_limit() {
+    let mut bytes = storage_info().to_bytes().unwrap();
+    let len = bytes.len() as u64;
+    bytes.extend_from_slice(&[0xAA; 8]);
+
+    let mut payload = LimitedReader::new(&bytes[..], len);
+    assert_eq!(
+        StorageInfo::decode_from(&mut payload).unwrap(),
+        storage_info()
+    );
+    payload.expect_end().unwrap();
+    assert!(payload.read_ptp_u8().is_err());
+
+    let mut short = LimitedReader::new(&bytes[..], len + 4);
+    StorageInfo::decode_from(&mut short).unwrap();
+    assert_eq!(short.remaining_len(), 4);
+    assert!(short.expect_end().is_err());
+}
+
+fn assert_malformed<T: std::fmt::Debug>(res: Result<T, Error>) {
+    match res {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+// an element count of 65535 followed by a few bytes
+const HUGE_COUNT: [u8; 8] = [0xFF, 0xFF, 0, 0, 1, 2, 3, 4];
+
+#[test]
+fn huge_count_over_short_buffer() {
+    let mut cur = Cursor::new(&HUGE_COUNT[..]);
+    assert_malformed(cur.read_ptp_u32_vec());
+    assert_eq!(cur.position(), 8);
+
+    let mut slice = &HUGE_COUNT[..];
+    assert_malformed(slice.read_ptp_u8_vec());
+    assert!(slice.is_empty());
+
+    assert_malformed(Vec::<StorageInfo>::from_bytes(&[2, 0, 0, 0, 0, 0]));
+    assert_eq!(StorageInfo::MIN_ENCODED_LEN, 28);
+}
+
+#[test]
+fn huge_count_over_short_stream() {
+    // a stream that never ends, limited to the payload of a data phase
+    let stream = CountingReader::new(Cursor::new(HUGE_COUNT).chain(io::repeat(0xAB)));
+    let mut payload = LimitedReader::new(stream, 63);
+    assert_malformed(payload.read_ptp_u16_vec());
+    // the odd byte left is not consumed by the element that does not fit
+    assert_eq!(payload.remaining_len(), 1);
+    assert_eq!(payload.into_inner().count(), 62);
+
+    let mut take = Cursor::new(HUGE_COUNT).chain(io::repeat(0xAB)).take(64);
+    assert_malformed(take.read_ptp_u16_vec());
+    assert_eq!(take.limit(), 0);
+}
+
+#[test]
+fn any_io_read() {
+    // a reader the crate knows nothing about
+    struct Pipe(Vec<u8>);
+
+    impl io::Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let n = buf.len().min(self.0.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0.drain(..n);
+            Ok(n)
+        }
+    }
+
+    let mut pipe = Pipe(storage_info().to_bytes().unwrap());
+    assert_eq!(StorageInfo::decode_from(&mut pipe).unwrap(), storage_info());
+    assert!(pipe.0.is_empty());
+}
+
+#[test]
+fn count_within_remaining_bytes() {
+    let mut cur = Cursor::new(&[2, 0, 0, 0, 1, 0, 2, 0][..]);
+    assert_eq!(cur.read_ptp_u16_vec().unwrap(), vec![1, 2]);
+    cur.expect_end().unwrap();
+}
@@ -0,0 +1,275 @@
+#![allow(non_snake_case)]
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+use ptp::{
+    DataType, DeviceInfo, FormData, ObjectInfo, ObjectInfoRef, PropInfo, PropInfoSony, PtpDecode,
+    PtpEncode, PtpEvent, StorageInfo,
+};
+use std::io::Cursor;
+
+const TYPE_CODES: &[u16] = &[
+    0x0001, 0x0002, 0x0003, 0x0004, 0x0005, 0x0006, 0x0007, 0x0008, 0x0009, 0x000A, 0x4001, 0x4002,
+    0x4003, 0x4004, 0x4005, 0x4006, 0x4007, 0x4008, 0x4009, 0x400A, 0xFFFF,
+];
+
+// PTP strings hold at most 254 UTF-16 code units, which 127 chars never exceed
+fn ptp_string() -> impl Strategy<Value = String> {
+    "\\PC{0,127}"
+}
+
+fn array<T: Arbitrary>() -> impl Strategy<Value = Vec<T>> {
+    vec(any::<T>(), 0..16)
+}
+
+fn data_type_of(kind: u16) -> BoxedStrategy<DataType> {
+    match kind {
+        0x0001 => any::<i8>().prop_map(DataType::INT8).boxed(),
+        0x0002 => any::<u8>().prop_map(DataType::UINT8).boxed(),
+        0x0003 => any::<i16>().prop_map(DataType::INT16).boxed(),
+        0x0004 => any::<u16>().prop_map(DataType::UINT16).boxed(),
+        0x0005 => any::<i32>().prop_map(DataType::INT32).boxed(),
+        0x0006 => any::<u32>().prop_map(DataType::UINT32).boxed(),
+        0x0007 => any::<i64>().prop_map(DataType::INT64).boxed(),
+        0x0008 => any::<u64>().prop_map(DataType::UINT64).boxed(),
+        0x0009 => any::<i128>().prop_map(DataType::INT128).boxed(),
+        0x000A => any::<u128>().prop_map(DataType::UINT128).boxed(),
+        0x4001 => array::<i8>().prop_map(DataType::AINT8).boxed(),
+        0x4002 => array::<u8>().prop_map(DataType::AUINT8).boxed(),
+        0x4003 => array::<i16>().prop_map(DataType::AINT16).boxed(),
+        0x4004 => array::<u16>().prop_map(DataType::AUINT16).boxed(),
+        0x4005 => array::<i32>().prop_map(DataType::AINT32).boxed(),
+        0x4006 => array::<u32>().prop_map(DataType::AUINT32).boxed(),
+        0x4007 => array::<i64>().prop_map(DataType::AINT64).boxed(),
+        0x4008 => array::<u64>().prop_map(DataType::AUINT64).boxed(),
+        0x4009 => array::<i128>().prop_map(DataType::AINT128).boxed(),
+        0x400A => array::<u128>().prop_map(DataType::AUINT128).boxed(),
+        0xFFFF => ptp_string().prop_map(DataType::STR).boxed(),
+        _ => unreachable!(),
+    }
+}
+
+fn type_code() -> impl Strategy<Value = u16> {
+    proptest::sample::select(TYPE_CODES)
+}
+
+fn data_type() -> impl Strategy<Value = DataType> {
+    type_code().prop_flat_map(data_type_of)
+}
+
+fn form_of(kind: u16) -> impl Strategy<Value = FormData> {
+    prop_oneof![
+        Just(FormData::None),
+        (data_type_of(kind), data_type_of(kind), data_type_of(kind)).prop_map(
+            |(min_value, max_value, step)| FormData::Range {
+                min_value,
+                max_value,
+                step,
+            }
+        ),
+        vec(data_type_of(kind), 0..8).prop_map(|array| FormData::Enumeration { array }),
+    ]
+}
+
+prop_compose! {
+    fn device_info()(
+        Version in any::<u16>(),
+        VendorExID in any::<u32>(),
+        VendorExVersion in any::<u16>(),
+        VendorExtensionDesc in ptp_string(),
+        FunctionalMode in any::<u16>(),
+        OperationsSupported in array::<u16>(),
+        EventsSupported in array::<u16>(),
+        DevicePropertiesSupported in array::<u16>(),
+        CaptureFormats in array::<u16>(),
+        ImageFormats in array::<u16>(),
+        Manufacturer in ptp_string(),
+        Model in ptp_string(),
+        DeviceVersion in ptp_string(),
+        SerialNumber in ptp_string(),
+    ) -> DeviceInfo {
+        DeviceInfo {
+            Version,
+            VendorExID,
+            VendorExVersion,
+            VendorExtensionDesc,
+            FunctionalMode,
+            OperationsSupported,
+            EventsSupported,
+            DevicePropertiesSupported,
+            CaptureFormats,
+            ImageFormats,
+            Manufacturer,
+            Model,
+            DeviceVersion,
+            SerialNumber,
+        }
+    }
+}
+
+prop_compose! {
+    fn object_info()(
+        header in any::<(u32, u16, u16, u32, u16, u32)>(),
+        image in any::<(u32, u32, u32, u32, u32)>(),
+        association in any::<(u32, u16, u32, u32)>(),
+        Filename in ptp_string(),
+        CaptureDate in ptp_string(),
+        ModificationDate in ptp_string(),
+        Keywords in ptp_string(),
+    ) -> ObjectInfo {
+        let (StorageID, ObjectFormat, ProtectionStatus, ObjectCompressedSize, ThumbFormat, ThumbCompressedSize) = header;
+        let (ThumbPixWidth, ThumbPixHeight, ImagePixWidth, ImagePixHeight, ImageBitDepth) = image;
+        let (ParentObject, AssociationType, AssociationDesc, SequenceNumber) = association;
+        ObjectInfo {
+            StorageID,
+            ObjectFormat,
+            ProtectionStatus,
+            ObjectCompressedSize,
+            ThumbFormat,
+            ThumbCompressedSize,
+            ThumbPixWidth,
+            ThumbPixHeight,
+            ImagePixWidth,
+            ImagePixHeight,
+            ImageBitDepth,
+            ParentObject,
+            AssociationType,
+            AssociationDesc,
+            SequenceNumber,
+            Filename,
+            CaptureDate,
+            ModificationDate,
+            Keywords,
+        }
+    }
+}
+
+prop_compose! {
+    fn storage_info()(
+        StorageType in any::<u16>(),
+        FilesystemType in any::<u16>(),
+        AccessCapability in any::<u16>(),
+        MaxCapacity in any::<u64>(),
+        FreeSpaceInBytes in any::<u64>(),
+        FreeSpaceInImages in any::<u32>(),
+        StorageDescription in ptp_string(),
+        VolumeLabel in ptp_string(),
+    ) -> StorageInfo {
+        StorageInfo {
+            StorageType,
+            FilesystemType,
+            AccessCapability,
+            MaxCapacity,
+            FreeSpaceInBytes,
+            FreeSpaceInImages,
+            StorageDescription,
+            VolumeLabel,
+        }
+    }
+}
+
+fn prop_info() -> impl Strategy<Value = PropInfo> {
+    type_code().prop_flat_map(|data_type| {
+        (
+            any::<u16>(),
+            any::<u8>(),
+            data_type_of(data_type),
+            data_type_of(data_type),
+            form_of(data_type),
+        )
+            .prop_map(
+                move |(property_code, get_set, factory_default, current, form)| PropInfo {
+                    property_code,
+                    data_type,
+                    get_set,
+                    factory_default,
+                    current,
+                    form,
+                },
+            )
+    })
+}
+
+fn prop_info_sony() -> impl Strategy<Value = PropInfoSony> {
+    (prop_info(), any::<u8>()).prop_map(|(info, is_enable)| PropInfoSony {
+        property_code: info.property_code,
+        data_type: info.data_type,
+        get_set: info.get_set,
+        is_enable,
+        factory_default: info.factory_default,
+        current: info.current,
+        form: info.form,
+    })
+}
+
+fn round_trip<T: PtpEncode + PtpDecode + PartialEq + std::fmt::Debug>(value: &T) {
+    let bytes = value.to_bytes().unwrap();
+    assert_eq!(&T::from_bytes(&bytes).unwrap(), value);
+}
+
+proptest! {
+    #[test]
+    fn data_type_round_trip(value in data_type()) {
+        let bytes = value.encode().unwrap();
+        let mut cur = Cursor::new(&bytes);
+        let decoded = DataType::read_type(value.type_code(), &mut cur).unwrap();
+        prop_assert_eq!(cur.position(), bytes.len() as u64);
+        prop_assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn device_info_round_trip(value in device_info()) {
+        round_trip(&value);
+    }
+
+    #[test]
+    fn object_info_round_trip(value in object_info()) {
+        round_trip(&value);
+    }
+
+    #[test]
+    fn object_info_view_matches_decode(value in object_info()) {
+        let bytes = value.to_bytes().unwrap();
+        let view = ObjectInfoRef::parse(&bytes).unwrap();
+        prop_assert!(view.filename().eq_str(&value.Filename));
+        prop_assert_eq!(view.to_object_info().unwrap(), value);
+    }
+
+    #[test]
+    fn storage_info_round_trip(value in storage_info()) {
+        round_trip(&value);
+    }
+
+    #[test]
+    fn prop_info_round_trip(value in prop_info()) {
+        round_trip(&value);
+    }
+
+    #[test]
+    fn prop_info_sony_round_trip(value in prop_info_sony()) {
+        round_trip(&value);
+    }
+
+    #[test]
+    fn decoders_never_panic(buf in vec(any::<u8>(), 0..256)) {
+        let _ = DeviceInfo::decode(&buf);
+        let _ = ObjectInfo::decode(&buf);
+        let _ = ObjectInfoRef::parse(&buf).map(|v| v.to_object_info());
+        let _ = StorageInfo::decode(&mut Cursor::new(&buf));
+        let _ = PropInfo::decode(&mut Cursor::new(&buf));
+        let _ = PtpEvent::decode(&buf);
+    }
+}
+
+#[test]
+fn container_shorter_than_header() {
+    // event container claiming a length of 4 bytes
+    let buf = [4, 0, 0, 0, 4, 0, 0x02, 0x40, 1, 0, 0, 0];
+    assert!(PtpEvent::decode(&buf).is_err());
+}
+
+#[test]
+fn strings_over_254_code_units() {
+    assert!(DataType::STR("x".repeat(254)).encode().is_ok());
+    assert!(DataType::STR("x".repeat(255)).encode().is_err());
+}
@@ -0,0 +1,129 @@
+#![cfg(feature = "serde")]
+
+use ptp::{DataType, FormData, PropInfo};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+
+// serializes to exactly `json`, and back to `value`
+fn assert_golden<T: Serialize + DeserializeOwned + PartialEq + Debug>(value: &T, json: &str) {
+    assert_eq!(serde_json::to_string(value).unwrap(), json);
+    assert_eq!(&serde_json::from_str::<T>(json).unwrap(), value);
+}
+
+#[test]
+fn data_type() {
+    assert_golden(&DataType::UNDEF, r#"{"type":"UNDEF"}"#);
+    assert_golden(&DataType::INT8(-5), r#"{"type":"INT8","value":-5}"#);
+    assert_golden(&DataType::UINT16(100), r#"{"type":"UINT16","value":100}"#);
+    assert_golden(
+        &DataType::UINT64(u64::MAX),
+        r#"{"type":"UINT64","value":18446744073709551615}"#,
+    );
+    assert_golden(
+        &DataType::AUINT8(vec![1, 2]),
+        r#"{"type":"AUINT8","value":[1,2]}"#,
+    );
+    assert_golden(&DataType::AINT32(vec![]), r#"{"type":"AINT32","value":[]}"#);
+    assert_golden(
+        &DataType::STR("F/2.8".to_owned()),
+        r#"{"type":"STR","value":"F/2.8"}"#,
+    );
+}
+
+#[test]
+fn form_data() {
+    assert_golden(&FormData::None, r#"{"form":"None"}"#);
+    assert_golden(
+        &FormData::Range {
+            min_value: DataType::UINT8(0),
+            max_value: DataType::UINT8(100),
+            step: DataType::UINT8(5),
+        },
+        concat!(
+            r#"{"form":"Range","min_value":{"type":"UINT8","value":0},"#,
+            r#""max_value":{"type":"UINT8","value":100},"step":{"type":"UINT8","value":5}}"#
+        ),
+    );
+    assert_golden(
+        &FormData::Enumeration {
+            array: vec![DataType::UINT16(1), DataType::UINT16(2)],
+        },
+        concat!(
+            r#"{"form":"Enumeration","array":[{"type":"UINT16","value":1},"#,
+            r#"{"type":"UINT16","value":2}]}"#
+        ),
+    );
+}
+
+#[test]
+fn prop_info() {
+    assert_golden(
+        &PropInfo {
+            property_code: 0x5001,
+            data_type: 0x0002,
+            get_set: 0,
+            factory_default: DataType::UINT8(100),
+            current: DataType::UINT8(50),
+            form: FormData::Range {
+                min_value: DataType::UINT8(0),
+                max_value: DataType::UINT8(100),
+                step: DataType::UINT8(1),
+            },
+        },
+        concat!(
+            r#"{"property_code":20481,"data_type":2,"get_set":0,"#,
+            r#""factory_default":{"type":"UINT8","value":100},"#,
+            r#""current":{"type":"UINT8","value":50},"#,
+            r#""form":{"form":"Range","min_value":{"type":"UINT8","value":0},"#,
+            r#""max_value":{"type":"UINT8","value":100},"step":{"type":"UINT8","value":1}}}"#
+        ),
+    );
+}
+
+#[test]
+fn data_type_128() {
+    assert_golden(&DataType::INT128(-5), r#"{"type":"INT128","value":"-5"}"#);
+    assert_golden(
+        &DataType::UINT128(u128::MAX),
+        r#"{"type":"UINT128","value":"340282366920938463463374607431768211455"}"#,
+    );
+    assert_golden(
+        &DataType::AINT128(vec![i128::MIN, 1]),
+        r#"{"type":"AINT128","value":["-170141183460469231731687303715884105728","1"]}"#,
+    );
+    assert_golden(
+        &DataType::AUINT128(vec![5, 6]),
+        r#"{"type":"AUINT128","value":["5","6"]}"#,
+    );
+    assert!(serde_json::from_str::<DataType>(r#"{"type":"UINT128","value":"-1"}"#).is_err());
+}
+
+#[test]
+fn data_type_value_first() {
+    for (json, value) in &[
+        (r#"{"value":100,"type":"UINT16"}"#, DataType::UINT16(100)),
+        (r#"{"value":"-5","type":"INT128"}"#, DataType::INT128(-5)),
+        (
+            r#"{"value":["5","6"],"type":"AUINT128"}"#,
+            DataType::AUINT128(vec![5, 6]),
+        ),
+    ] {
+        assert_eq!(&serde_json::from_str::<DataType>(json).unwrap(), value);
+    }
+}
+
+#[test]
+fn form_data_128() {
+    let form = FormData::Range {
+        min_value: DataType::UINT128(0),
+        max_value: DataType::UINT128(u128::MAX),
+        step: DataType::UINT128(1),
+    };
+    let json = serde_json::to_string(&form).unwrap();
+    assert_eq!(serde_json::from_str::<FormData>(&json).unwrap(), form);
+    let form = FormData::Enumeration {
+        array: vec![DataType::INT128(-1), DataType::AINT128(vec![2])],
+    };
+    let json = serde_json::to_string(&form).unwrap();
+    assert_eq!(serde_json::from_str::<FormData>(&json).unwrap(), form);
+}
@@ -0,0 +1,166 @@
+use ptp::vendor::sony::{SonyExtDeviceInfo, SonyLiveViewFrame};
+use ptp::{DataType, Error, FormData, PropInfoSony};
+
+const JPEG: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, 0xFF, 0xD9];
+
+// header giving the offset and size of the image, focus frame data, then the image
+fn live_view(offset: u32, size: u32) -> Vec<u8> {
+    let mut buf = vec![];
+    buf.extend_from_slice(&offset.to_le_bytes());
+    buf.extend_from_slice(&size.to_le_bytes());
+    buf.resize(offset as usize, 0xAA);
+    buf.extend_from_slice(JPEG);
+    buf
+}
+
+#[test]
+fn decode_live_view() {
+    let frame = SonyLiveViewFrame::decode(&live_view(136, JPEG.len() as u32)).unwrap();
+    assert_eq!(frame.jpeg, JPEG);
+    assert_eq!(frame.info, vec![0xAA; 128]);
+}
+
+#[test]
+fn decode_live_view_without_info() {
+    let frame = SonyLiveViewFrame::decode(&live_view(8, JPEG.len() as u32)).unwrap();
+    assert_eq!(frame.jpeg, JPEG);
+    assert!(frame.info.is_empty());
+}
+
+#[test]
+fn live_view_image_past_end() {
+    for &(offset, size) in &[(136, 100), (4, 8), (8, u32::MAX)] {
+        let mut buf = live_view(136, JPEG.len() as u32);
+        buf[..4].copy_from_slice(&u32::to_le_bytes(offset));
+        buf[4..8].copy_from_slice(&u32::to_le_bytes(size));
+        match SonyLiveViewFrame::decode(&buf) {
+            Err(Error::Malformed(_)) => {}
+            other => panic!("expected Malformed, got {:?}", other),
+        }
+    }
+    assert!(SonyLiveViewFrame::decode(&[0; 6]).is_err());
+}
+
+fn assert_malformed<T: std::fmt::Debug>(res: Result<T, Error>) {
+    match res {
+        Err(Error::Malformed(_)) => {}
+        other => panic!("expected Malformed, got {:?}", other),
+    }
+}
+
+// protocol version 200, two properties and one control
+const EXT_DEVICE_INFO: &[u8] = &[
+    0xC8, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0D, 0xD2, 0x1E, 0xD2, 0x01, 0x00, 0x00, 0x00, 0xC1, 0xD2,
+];
+
+#[test]
+fn decode_ext_device_info() {
+    assert_eq!(
+        SonyExtDeviceInfo::decode(EXT_DEVICE_INFO).unwrap(),
+        SonyExtDeviceInfo {
+            version: 200,
+            properties: vec![0xD20D, 0xD21E],
+            controls: vec![0xD2C1],
+        }
+    );
+
+    // older bodies stop after the property list
+    let info = SonyExtDeviceInfo::decode(&EXT_DEVICE_INFO[..10]).unwrap();
+    assert_eq!(info.properties, vec![0xD20D, 0xD21E]);
+    assert!(info.controls.is_empty());
+
+    assert_malformed(SonyExtDeviceInfo::decode(&EXT_DEVICE_INFO[..8]));
+    assert_malformed(SonyExtDeviceInfo::decode(&EXT_DEVICE_INFO[..12]));
+}
+
+// ShutterSpeed, read-write and enabled, currently 1/250
+const SHUTTER_SPEED: &[u8] = &[
+    0x0D, 0xD2, 0x06, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0xFA, 0x00, 0x01, 0x00, 0x00,
+];
+
+fn iso() -> PropInfoSony {
+    PropInfoSony {
+        property_code: 0xD21E,
+        data_type: 0x0006,
+        get_set: 1,
+        is_enable: 1,
+        factory_default: DataType::UINT32(0x00FF_FFFF),
+        current: DataType::UINT32(400),
+        form: FormData::Enumeration {
+            array: vec![DataType::UINT32(100), DataType::UINT32(400)],
+        },
+    }
+}
+
+fn prop_list(count: u64, descriptors: &[&[u8]]) -> Vec<u8> {
+    let mut buf = count.to_le_bytes().to_vec();
+    for d in descriptors {
+        buf.extend_from_slice(d);
+    }
+    buf
+}
+
+#[test]
+fn decode_prop_list() {
+    let iso_bytes = iso().encode().unwrap();
+    let props = PropInfoSony::decode_all(&prop_list(2, &[SHUTTER_SPEED, &iso_bytes])).unwrap();
+    assert_eq!(
+        props[0],
+        PropInfoSony {
+            property_code: 0xD20D,
+            data_type: 0x0006,
+            get_set: 1,
+            is_enable: 1,
+            factory_default: DataType::UINT32(0),
+            current: DataType::UINT32(0x0001_00FA),
+            form: FormData::None,
+        }
+    );
+    assert_eq!(props[1], iso());
+
+    assert!(PropInfoSony::decode_all(&prop_list(0, &[]))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn truncated_prop_list() {
+    let iso_bytes = iso().encode().unwrap();
+    let buf = prop_list(2, &[SHUTTER_SPEED, &iso_bytes]);
+    assert_malformed(PropInfoSony::decode_all(&buf[..buf.len() - 1]));
+    assert_malformed(PropInfoSony::decode_all(&buf[..6]));
+    // a count the payload cannot hold
+    assert_malformed(PropInfoSony::decode_all(&prop_list(100, &[SHUTTER_SPEED])));
+    // trailing data after the last descriptor
+    assert_malformed(PropInfoSony::decode_all(&prop_list(
+        1,
+        &[SHUTTER_SPEED, &iso_bytes],
+    )));
+}
+
+#[test]
+fn vendor_typed_prop_in_list() {
+    // a property of datatype 0xD001, whose values cannot be sized
+    let vendor: &[u8] = &[
+        0x2A, 0xD2, 0x01, 0xD0, 0x00, 0x01, 0x12, 0x34, 0x56, 0x78, 0x00,
+    ];
+    let iso_bytes = iso().encode().unwrap();
+    let props =
+        PropInfoSony::decode_all(&prop_list(3, &[SHUTTER_SPEED, vendor, &iso_bytes])).unwrap();
+
+    // the list ends at the vendor-typed descriptor
+    assert_eq!(props.len(), 2);
+    assert_eq!(props[0].property_code, 0xD20D);
+    assert_eq!(
+        props[1],
+        PropInfoSony {
+            property_code: 0xD22A,
+            data_type: 0xD001,
+            get_set: 0,
+            is_enable: 1,
+            factory_default: DataType::UNDEF,
+            current: DataType::UNDEF,
+            form: FormData::None,
+        }
+    );
+}
@@ -0,0 +1,120 @@
+use ptp::{DeviceInfo, Error, VendorExtension, VendorExtensions, VendorId};
+
+fn extension(domain: &str, version: &str) -> VendorExtension {
+    VendorExtension {
+        domain: domain.to_owned(),
+        version: version.to_owned(),
+    }
+}
+
+fn device_info(id: u32, desc: &str, manufacturer: &str) -> DeviceInfo {
+    DeviceInfo {
+        Version: 100,
+        VendorExID: id,
+        VendorExVersion: 100,
+        VendorExtensionDesc: desc.to_owned(),
+        FunctionalMode: 0,
+        OperationsSupported: vec![],
+        EventsSupported: vec![],
+        DevicePropertiesSupported: vec![],
+        CaptureFormats: vec![],
+        ImageFormats: vec![],
+        Manufacturer: manufacturer.to_owned(),
+        Model: String::new(),
+        DeviceVersion: String::new(),
+        SerialNumber: String::new(),
+    }
+}
+
+#[test]
+fn parse_mtp_description() {
+    let ext = VendorExtensions::parse(6, 100, "microsoft.com: 1.0; android.com: 1.0;");
+    assert_eq!(ext.id, VendorId::Microsoft);
+    assert_eq!(ext.version, 100);
+    assert_eq!(
+        ext.extensions,
+        vec![
+            extension("microsoft.com", "1.0"),
+            extension("android.com", "1.0")
+        ]
+    );
+    assert!(ext.is_mtp());
+    assert!(ext.has("Android.com"));
+    assert_eq!(ext.get("android.com").unwrap().version, "1.0");
+    assert_eq!(ext.vendor(), VendorId::Microsoft);
+}
+
+#[test]
+fn parse_whitespace_and_missing_version() {
+    let ext = VendorExtensions::parse(0x0B, 100, "  canon.com ;; microsoft.com:1.0 ;  ");
+    assert_eq!(
+        ext.extensions,
+        vec![
+            extension("canon.com", ""),
+            extension("microsoft.com", "1.0")
+        ]
+    );
+    assert_eq!(ext.extensions[0].to_string(), "canon.com");
+    assert_eq!(ext.extensions[1].to_string(), "microsoft.com: 1.0");
+    assert!(ext.is_mtp());
+    assert_eq!(ext.vendor(), VendorId::Canon);
+}
+
+#[test]
+fn parse_empty_description() {
+    let ext = VendorExtensions::parse(0x11, 200, "");
+    assert!(ext.extensions.is_empty());
+    assert!(!ext.is_mtp());
+    assert_eq!(ext.vendor(), VendorId::Sony);
+}
+
+#[test]
+fn vendor_from_extension_domain() {
+    // Nikon bodies in MTP mode report Microsoft's ID but name their own extension set
+    let ext = VendorExtensions::parse(6, 100, "microsoft.com: 1.0; nikon.com: 1.0;");
+    assert!(ext.is_mtp());
+    assert_eq!(ext.vendor(), VendorId::Nikon);
+
+    let ext = VendorExtensions::parse(0x1234, 100, "fujifilm.co.jp: 1.0;");
+    assert_eq!(ext.id, VendorId::Unknown(0x1234));
+    assert_eq!(ext.vendor(), VendorId::Fuji);
+}
+
+#[test]
+fn olympus_detected_without_vendor_id() {
+    let info = device_info(6, "microsoft.com: 1.0;", "OM Digital Solutions");
+    assert_eq!(info.vendor(), VendorId::Olympus);
+
+    let info = device_info(6, "microsoft.com: 1.0; olympus.com: 1.0;", "");
+    assert_eq!(info.vendor(), VendorId::Olympus);
+
+    let info = device_info(6, "microsoft.com: 1.0;", "Google");
+    assert_eq!(info.vendor(), VendorId::Microsoft);
+}
+
+#[test]
+fn vendor_id_round_trip() {
+    for id in 0..0x20 {
+        assert_eq!(VendorId::from_u32(id).to_u32(), id);
+    }
+    assert_eq!(VendorId::from(0x0A), VendorId::Nikon);
+}
+
+#[test]
+fn response_display() {
+    assert_eq!(Error::Response(0x2019).to_string(), "DeviceBusy (0x2019)");
+    assert_eq!(
+        Error::Response(0xA801).to_string(),
+        "InvalidObjectPropCode (0xa801)"
+    );
+    // vendors give the same code different meanings
+    assert_eq!(
+        Error::Response(0xA001).to_string(),
+        "Canon UnknownCommand or Nikon HardwareError (0xa001)"
+    );
+    assert_eq!(
+        Error::Response(0xA102).to_string(),
+        "Canon NotReady (0xa102)"
+    );
+    assert_eq!(Error::Response(0xAFFF).to_string(), "Unknown (0xafff)");
+}